@@ -7,6 +7,12 @@ pub struct Aabb {
     pub max: Point3,
 }
 
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Aabb {
     pub fn new() -> Self {
         Aabb {
@@ -19,6 +25,11 @@ impl Aabb {
         Aabb { min: a, max: b }
     }
 
+    pub fn surface_area(&self) -> f64 {
+        let d = self.max - self.min;
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
     pub fn hit(&self, r: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
         for a in 0..3 {
             let t0_numerator = match a {
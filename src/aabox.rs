@@ -0,0 +1,134 @@
+use crate::aabb::Aabb;
+use crate::aarect::{XyRect, XzRect, YzRect};
+use crate::hittable::{HitRecord, Hittable};
+use crate::hittable_list::HittableList;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Point3;
+use std::sync::Arc;
+
+// An axis-aligned box made of six rectangles, spanning the corners p0 and p1.
+pub struct AaBox {
+    box_min: Point3,
+    box_max: Point3,
+    sides: HittableList,
+}
+
+impl AaBox {
+    pub fn new(p0: Point3, p1: Point3, ptr: Arc<dyn Material>) -> Self {
+        let mut sides = HittableList::new();
+
+        sides.add(Arc::new(XyRect::new(
+            p0.x,
+            p1.x,
+            p0.y,
+            p1.y,
+            p1.z,
+            ptr.clone(),
+        )));
+        sides.add(Arc::new(FlipFace(Arc::new(XyRect::new(
+            p0.x,
+            p1.x,
+            p0.y,
+            p1.y,
+            p0.z,
+            ptr.clone(),
+        )))));
+
+        sides.add(Arc::new(XzRect::new(
+            p0.x,
+            p1.x,
+            p0.z,
+            p1.z,
+            p1.y,
+            ptr.clone(),
+        )));
+        sides.add(Arc::new(FlipFace(Arc::new(XzRect::new(
+            p0.x,
+            p1.x,
+            p0.z,
+            p1.z,
+            p0.y,
+            ptr.clone(),
+        )))));
+
+        sides.add(Arc::new(YzRect::new(
+            p0.y,
+            p1.y,
+            p0.z,
+            p1.z,
+            p1.x,
+            ptr.clone(),
+        )));
+        sides.add(Arc::new(FlipFace(Arc::new(YzRect::new(
+            p0.y, p1.y, p0.z, p1.z, p0.x, ptr,
+        )))));
+
+        Self {
+            box_min: p0,
+            box_max: p1,
+            sides,
+        }
+    }
+}
+
+// A rectangle facing the other way, for the sides of the box on its minimum corner, so
+// that every side faces out.
+struct FlipFace(Arc<dyn Hittable>);
+
+impl Hittable for FlipFace {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut rec = self.0.hit(r, t_min, t_max)?;
+        rec.front_face = !rec.front_face;
+        Some(rec)
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
+        self.0.bounding_box(t0, t1)
+    }
+}
+
+impl Hittable for AaBox {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.sides.hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<Aabb> {
+        Some(Aabb::from_points(self.box_min, self.box_max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::texture::SolidColor;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn test_box_hits_nearest_side() {
+        let cube = AaBox::new(
+            Point3::new(-1.0, -1.0, -1.0),
+            Point3::new(1.0, 2.0, 1.0),
+            Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+                0.5, 0.5, 0.5,
+            )))),
+        );
+
+        let r = Ray::new(Point3::new(-5.0, 0.5, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let rec = cube.hit(&r, 0.001, f64::INFINITY).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-9);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(-1.0, 0.0, 0.0));
+
+        // From inside, the ray leaves through the top.
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), 0.0);
+        let rec = cube.hit(&r, 0.001, f64::INFINITY).unwrap();
+        assert!((rec.t - 2.0).abs() < 1e-9);
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(0.0, -1.0, 0.0));
+
+        let r = Ray::new(Point3::new(-5.0, 3.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert!(cube.hit(&r, 0.001, f64::INFINITY).is_none());
+    }
+}
@@ -0,0 +1,279 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::rtweekend::random_double_range;
+use crate::vec3::{Point3, Vec3};
+use std::sync::Arc;
+
+// Half thickness given to the bounding box of a zero-thickness rectangle.
+const PAD: f64 = 0.0001;
+
+pub struct XyRect {
+    pub x0: f64,
+    pub x1: f64,
+    pub y0: f64,
+    pub y1: f64,
+    pub k: f64,
+    pub mp: Arc<dyn Material>,
+}
+
+impl XyRect {
+    pub fn new(x0: f64, x1: f64, y0: f64, y1: f64, k: f64, mp: Arc<dyn Material>) -> Self {
+        Self {
+            x0,
+            x1,
+            y0,
+            y1,
+            k,
+            mp,
+        }
+    }
+}
+
+impl Hittable for XyRect {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let t = (self.k - r.origin.z) / r.direction.z;
+        if t < t_min || t > t_max {
+            return None;
+        }
+        let x = r.origin.x + t * r.direction.x;
+        let y = r.origin.y + t * r.direction.y;
+        if x < self.x0 || x > self.x1 || y < self.y0 || y > self.y1 {
+            return None;
+        }
+        let mut rec = HitRecord {
+            p: r.at(t),
+            t,
+            u: (x - self.x0) / (self.x1 - self.x0),
+            v: (y - self.y0) / (self.y1 - self.y0),
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            tangent: Vec3::new(self.x1 - self.x0, 0.0, 0.0),
+            bitangent: Vec3::new(0.0, self.y1 - self.y0, 0.0),
+            front_face: false,
+            mat_ptr: Arc::clone(&self.mp),
+        };
+        rec.set_face_normal(r, Vec3::new(0.0, 0.0, 1.0));
+        Some(rec)
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<Aabb> {
+        Some(Aabb::from_points(
+            Point3::new(self.x0, self.y0, self.k - PAD),
+            Point3::new(self.x1, self.y1, self.k + PAD),
+        ))
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        let Some(rec) = self.hit(&Ray::new(*origin, *direction, 0.0), 0.001, f64::INFINITY) else {
+            return 0.0;
+        };
+
+        let area = (self.x1 - self.x0) * (self.y1 - self.y0);
+        let distance_squared = rec.t * rec.t * direction.length_squared();
+        let cosine = (direction.dot(rec.normal) / direction.length()).abs();
+        distance_squared / (cosine * area)
+    }
+
+    fn random(&self, origin: &Point3) -> Vec3 {
+        let random_point = Point3::new(
+            random_double_range(self.x0, self.x1),
+            random_double_range(self.y0, self.y1),
+            self.k,
+        );
+        random_point - *origin
+    }
+}
+
+pub struct XzRect {
+    pub x0: f64,
+    pub x1: f64,
+    pub z0: f64,
+    pub z1: f64,
+    pub k: f64,
+    pub mp: Arc<dyn Material>,
+}
+
+impl XzRect {
+    pub fn new(x0: f64, x1: f64, z0: f64, z1: f64, k: f64, mp: Arc<dyn Material>) -> Self {
+        Self {
+            x0,
+            x1,
+            z0,
+            z1,
+            k,
+            mp,
+        }
+    }
+}
+
+impl Hittable for XzRect {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let t = (self.k - r.origin.y) / r.direction.y;
+        if t < t_min || t > t_max {
+            return None;
+        }
+        let x = r.origin.x + t * r.direction.x;
+        let z = r.origin.z + t * r.direction.z;
+        if x < self.x0 || x > self.x1 || z < self.z0 || z > self.z1 {
+            return None;
+        }
+        let mut rec = HitRecord {
+            p: r.at(t),
+            t,
+            u: (x - self.x0) / (self.x1 - self.x0),
+            v: (z - self.z0) / (self.z1 - self.z0),
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            tangent: Vec3::new(self.x1 - self.x0, 0.0, 0.0),
+            bitangent: Vec3::new(0.0, 0.0, self.z1 - self.z0),
+            front_face: false,
+            mat_ptr: Arc::clone(&self.mp),
+        };
+        rec.set_face_normal(r, Vec3::new(0.0, 1.0, 0.0));
+        Some(rec)
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<Aabb> {
+        Some(Aabb::from_points(
+            Point3::new(self.x0, self.k - PAD, self.z0),
+            Point3::new(self.x1, self.k + PAD, self.z1),
+        ))
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        let Some(rec) = self.hit(&Ray::new(*origin, *direction, 0.0), 0.001, f64::INFINITY) else {
+            return 0.0;
+        };
+
+        let area = (self.x1 - self.x0) * (self.z1 - self.z0);
+        let distance_squared = rec.t * rec.t * direction.length_squared();
+        let cosine = (direction.dot(rec.normal) / direction.length()).abs();
+        distance_squared / (cosine * area)
+    }
+
+    fn random(&self, origin: &Point3) -> Vec3 {
+        let random_point = Point3::new(
+            random_double_range(self.x0, self.x1),
+            self.k,
+            random_double_range(self.z0, self.z1),
+        );
+        random_point - *origin
+    }
+}
+
+pub struct YzRect {
+    pub y0: f64,
+    pub y1: f64,
+    pub z0: f64,
+    pub z1: f64,
+    pub k: f64,
+    pub mp: Arc<dyn Material>,
+}
+
+impl YzRect {
+    pub fn new(y0: f64, y1: f64, z0: f64, z1: f64, k: f64, mp: Arc<dyn Material>) -> Self {
+        Self {
+            y0,
+            y1,
+            z0,
+            z1,
+            k,
+            mp,
+        }
+    }
+}
+
+impl Hittable for YzRect {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let t = (self.k - r.origin.x) / r.direction.x;
+        if t < t_min || t > t_max {
+            return None;
+        }
+        let y = r.origin.y + t * r.direction.y;
+        let z = r.origin.z + t * r.direction.z;
+        if y < self.y0 || y > self.y1 || z < self.z0 || z > self.z1 {
+            return None;
+        }
+        let mut rec = HitRecord {
+            p: r.at(t),
+            t,
+            u: (y - self.y0) / (self.y1 - self.y0),
+            v: (z - self.z0) / (self.z1 - self.z0),
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            tangent: Vec3::new(0.0, self.y1 - self.y0, 0.0),
+            bitangent: Vec3::new(0.0, 0.0, self.z1 - self.z0),
+            front_face: false,
+            mat_ptr: Arc::clone(&self.mp),
+        };
+        rec.set_face_normal(r, Vec3::new(1.0, 0.0, 0.0));
+        Some(rec)
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<Aabb> {
+        Some(Aabb::from_points(
+            Point3::new(self.k - PAD, self.y0, self.z0),
+            Point3::new(self.k + PAD, self.y1, self.z1),
+        ))
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        let Some(rec) = self.hit(&Ray::new(*origin, *direction, 0.0), 0.001, f64::INFINITY) else {
+            return 0.0;
+        };
+
+        let area = (self.y1 - self.y0) * (self.z1 - self.z0);
+        let distance_squared = rec.t * rec.t * direction.length_squared();
+        let cosine = (direction.dot(rec.normal) / direction.length()).abs();
+        distance_squared / (cosine * area)
+    }
+
+    fn random(&self, origin: &Point3) -> Vec3 {
+        let random_point = Point3::new(
+            self.k,
+            random_double_range(self.y0, self.y1),
+            random_double_range(self.z0, self.z1),
+        );
+        random_point - *origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::texture::SolidColor;
+
+    fn gray() -> Arc<dyn Material> {
+        Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+            0.5, 0.5, 0.5,
+        ))))
+    }
+
+    #[test]
+    fn test_rect_hit_and_uv() {
+        let rect = XyRect::new(0.0, 2.0, 0.0, 4.0, 1.0, gray());
+        let r = Ray::new(Point3::new(0.5, 3.0, -1.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let rec = rect.hit(&r, 0.001, f64::INFINITY).unwrap();
+        assert!((rec.t - 2.0).abs() < 1e-9);
+        assert!((rec.u - 0.25).abs() < 1e-9 && (rec.v - 0.75).abs() < 1e-9);
+        // Seen from below, the normal faces back at the ray.
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, -1.0));
+
+        assert!(rect.hit(&r, 0.001, 1.5).is_none());
+        let outside = Ray::new(Point3::new(2.5, 3.0, -1.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        assert!(rect.hit(&outside, 0.001, f64::INFINITY).is_none());
+
+        let rect = XzRect::new(0.0, 2.0, 0.0, 4.0, 1.0, gray());
+        let r = Ray::new(Point3::new(1.5, 3.0, 1.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let rec = rect.hit(&r, 0.001, f64::INFINITY).unwrap();
+        assert!(rec.front_face);
+        assert!((rec.u - 0.75).abs() < 1e-9 && (rec.v - 0.25).abs() < 1e-9);
+
+        let rect = YzRect::new(0.0, 2.0, 0.0, 4.0, 1.0, gray());
+        let r = Ray::new(Point3::new(3.0, 0.5, 2.0), Vec3::new(-1.0, 0.0, 0.0), 0.0);
+        let rec = rect.hit(&r, 0.001, f64::INFINITY).unwrap();
+        assert!(rec.front_face);
+        assert!((rec.u - 0.25).abs() < 1e-9 && (rec.v - 0.5).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,80 @@
+use crate::ray::Ray;
+use crate::vec3::{Color, Vec3};
+
+pub trait Background: Send + Sync {
+    fn value(&self, r: &Ray) -> Color;
+
+    // A direction to sample toward, or None when this background is not worth sampling.
+    fn random(&self) -> Option<Vec3> {
+        None
+    }
+
+    // Density, with respect to solid angle, of `random` returning `direction`.
+    fn pdf_value(&self, _direction: &Vec3) -> f64 {
+        0.0
+    }
+}
+
+pub struct SkyGradient {
+    horizon: Color,
+    zenith: Color,
+}
+
+impl Default for SkyGradient {
+    fn default() -> Self {
+        Self::new(Color::new(1.0, 1.0, 1.0), Color::new(0.5, 0.7, 1.0))
+    }
+}
+
+impl SkyGradient {
+    pub fn new(horizon: Color, zenith: Color) -> Self {
+        Self { horizon, zenith }
+    }
+}
+
+impl Background for SkyGradient {
+    fn value(&self, r: &Ray) -> Color {
+        let unit_direction = r.direction.unit_vector();
+        let t = 0.5 * (unit_direction.y + 1.0);
+        (1.0 - t) * self.horizon + t * self.zenith
+    }
+}
+
+pub struct SolidBackground {
+    color: Color,
+}
+
+impl SolidBackground {
+    pub fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Background for SolidBackground {
+    fn value(&self, _r: &Ray) -> Color {
+        self.color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3::Point3;
+
+    #[test]
+    fn test_gradient_blends_from_horizon_to_zenith() {
+        let sky = SkyGradient::default();
+        let toward = |d: Vec3| sky.value(&Ray::new(Point3::default(), d, 0.0));
+        assert_eq!(toward(Vec3::new(0.0, 3.0, 0.0)), Color::new(0.5, 0.7, 1.0));
+        // Level rays sit halfway between the colors at the horizon and the zenith.
+        assert_eq!(
+            toward(Vec3::new(2.0, 0.0, 0.0)),
+            Color::new(0.75, 0.85, 1.0)
+        );
+        assert_eq!(toward(Vec3::new(0.0, -1.0, 0.0)), Color::new(1.0, 1.0, 1.0));
+
+        let solid = SolidBackground::new(Color::new(0.1, 0.2, 0.3));
+        let r = Ray::new(Point3::default(), Vec3::new(0.3, -0.2, 1.0), 0.0);
+        assert_eq!(solid.value(&r), Color::new(0.1, 0.2, 0.3));
+    }
+}
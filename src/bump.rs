@@ -0,0 +1,221 @@
+use crate::hittable::HitRecord;
+use crate::material::{Material, ScatterRecord};
+use crate::nested::Medium;
+use crate::ray::Ray;
+use crate::texture::Texture;
+use crate::vec3::{Color, Point3, Vec3};
+use std::sync::Arc;
+
+// Finite difference step for the height gradient, in texture units.
+const DELTA: f64 = 1e-3;
+
+// Shades `base` with normals read from a tangent-space normal map, whose red, green and
+// blue channels give the tangent, bitangent and normal components mapped to [0, 1]. Load
+// image maps with `ImageTexture::open_linear`.
+pub struct NormalMap {
+    pub base: Arc<dyn Material>,
+    pub map: Arc<dyn Texture>,
+    pub strength: f64,
+}
+
+impl NormalMap {
+    pub fn new(base: Arc<dyn Material>, map: Arc<dyn Texture>) -> Self {
+        Self {
+            base,
+            map,
+            strength: 1.0,
+        }
+    }
+
+    // Scales the tangential part of the mapped normals; 0 leaves the surface flat.
+    pub fn with_strength(mut self, strength: f64) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    fn shade(&self, rec: &HitRecord) -> HitRecord {
+        let (t, b, n) = tangent_frame(rec);
+        let m = 2.0 * self.map.value(rec.u, rec.v, &rec.p) - Color::new(1.0, 1.0, 1.0);
+        let normal = self.strength * (m.x * t + m.y * b) + m.z * n;
+        with_shading_normal(rec, normal)
+    }
+}
+
+// Shades `base` as if its surface were displaced along the normal by `scale` times the
+// height texture, averaged over its channels.
+pub struct BumpMap {
+    pub base: Arc<dyn Material>,
+    pub height: Arc<dyn Texture>,
+    pub scale: f64,
+}
+
+impl BumpMap {
+    pub fn new(base: Arc<dyn Material>, height: Arc<dyn Texture>, scale: f64) -> Self {
+        Self {
+            base,
+            height,
+            scale,
+        }
+    }
+
+    fn shade(&self, rec: &HitRecord) -> HitRecord {
+        let (t, b, n) = tangent_frame(rec);
+        let height = |u: f64, v: f64, p: Point3| {
+            let c = self.height.value(u, v, &p);
+            (c.x + c.y + c.z) / 3.0
+        };
+        let h = height(rec.u, rec.v, rec.p);
+        let dhdu = (height(rec.u + DELTA, rec.v, rec.p + DELTA * rec.tangent) - h) / DELTA;
+        let dhdv = (height(rec.u, rec.v + DELTA, rec.p + DELTA * rec.bitangent) - h) / DELTA;
+        // The slopes per unit of distance along the surface, where u or v moves at all.
+        let per_length = |slope: f64, dpd: Vec3| {
+            let length = dpd.length();
+            if length > 0.0 { slope / length } else { 0.0 }
+        };
+        let slope = per_length(dhdu, rec.tangent) * t + per_length(dhdv, rec.bitangent) * b;
+        with_shading_normal(rec, n - self.scale * slope)
+    }
+}
+
+// An orthonormal tangent, bitangent and outward normal at the hit, keeping the
+// handedness of the surface's u and v directions.
+fn tangent_frame(rec: &HitRecord) -> (Vec3, Vec3, Vec3) {
+    let n = if rec.front_face {
+        rec.normal
+    } else {
+        -rec.normal
+    };
+    let t = (rec.tangent - rec.tangent.dot(n) * n).unit_vector();
+    let b = n.cross(t);
+    let b = if b.dot(rec.bitangent) < 0.0 { -b } else { b };
+    (t, b, n)
+}
+
+// A copy of the hit for the wrapped material, shaded by the outward `normal`. The caller
+// keeps the original, so rays leaving the surface are still judged by the geometric
+// normal.
+fn with_shading_normal(rec: &HitRecord, normal: Vec3) -> HitRecord {
+    let normal = normal.unit_vector();
+    let mut shaded = rec.clone();
+    shaded.normal = if rec.front_face { normal } else { -normal };
+    shaded
+}
+
+impl Material for NormalMap {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        self.base.scatter(r_in, &self.shade(rec), srec)
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        self.base.scattering_pdf(r_in, &self.shade(rec), scattered)
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, srec: &ScatterRecord, scattered: &Ray) -> Color {
+        self.base.eval(r_in, &self.shade(rec), srec, scattered)
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Point3) -> Color {
+        self.base.emitted(u, v, p)
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        self.base.medium()
+    }
+
+    fn opacity(&self, u: f64, v: f64, p: &Point3) -> f64 {
+        self.base.opacity(u, v, p)
+    }
+}
+
+impl Material for BumpMap {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        self.base.scatter(r_in, &self.shade(rec), srec)
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        self.base.scattering_pdf(r_in, &self.shade(rec), scattered)
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, srec: &ScatterRecord, scattered: &Ray) -> Color {
+        self.base.eval(r_in, &self.shade(rec), srec, scattered)
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Point3) -> Color {
+        self.base.emitted(u, v, p)
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        self.base.medium()
+    }
+
+    fn opacity(&self, u: f64, v: f64, p: &Point3) -> f64 {
+        self.base.opacity(u, v, p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::texture::SolidColor;
+
+    // A height rising along u.
+    struct Ramp;
+
+    impl Texture for Ramp {
+        fn value(&self, u: f64, _v: f64, _p: &Point3) -> Color {
+            Color::new(u, u, u)
+        }
+    }
+
+    fn hit(front_face: bool) -> HitRecord {
+        HitRecord {
+            p: Point3::new(0.0, 0.0, 0.0),
+            normal: Vec3::new(0.0, 0.0, if front_face { 1.0 } else { -1.0 }),
+            tangent: Vec3::new(1.0, 0.0, 0.0),
+            bitangent: Vec3::new(0.0, 1.0, 0.0),
+            t: 1.0,
+            u: 0.5,
+            v: 0.5,
+            mat_ptr: Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+                0.5, 0.5, 0.5,
+            )))),
+            front_face,
+        }
+    }
+
+    #[test]
+    fn test_flat_normal_map_keeps_normal() {
+        let flat = NormalMap::new(
+            hit(true).mat_ptr,
+            Arc::new(SolidColor::from_rgb(0.5, 0.5, 1.0)),
+        );
+        for front_face in [true, false] {
+            let rec = hit(front_face);
+            assert!((flat.shade(&rec).normal - rec.normal).length() < 1e-9);
+        }
+
+        // Tilted toward +u on both sides of the surface.
+        let tilted = NormalMap::new(
+            hit(true).mat_ptr,
+            Arc::new(SolidColor::from_rgb(1.0, 0.5, 1.0)),
+        );
+        let n = tilted.shade(&hit(true)).normal;
+        assert!((n - Vec3::new(1.0, 0.0, 1.0).unit_vector()).length() < 1e-9);
+        assert!((tilted.shade(&hit(false)).normal + n).length() < 1e-9);
+    }
+
+    #[test]
+    fn test_bump_slope_tilts_normal_downhill() {
+        let bump = BumpMap::new(hit(true).mat_ptr, Arc::new(Ramp), 0.5);
+        let n = bump.shade(&hit(true)).normal;
+        // The height rises by 0.5 per unit of u, so the normal leans back along -u.
+        assert!((n - Vec3::new(-0.5, 0.0, 1.0).unit_vector()).length() < 1e-6);
+
+        // Stretched to two units of length per unit of u, the same ramp is half as steep.
+        let mut stretched = hit(true);
+        stretched.tangent = Vec3::new(2.0, 0.0, 0.0);
+        let n = bump.shade(&stretched).normal;
+        assert!((n - Vec3::new(-0.25, 0.0, 1.0).unit_vector()).length() < 1e-6);
+    }
+}
@@ -1,76 +1,305 @@
 use crate::aabb::{Aabb, surrounding_box};
 use crate::hittable::{HitRecord, Hittable};
 use crate::ray::Ray;
-use rand::Rng;
+use crate::rtweekend::random_double;
 use std::cmp::Ordering;
+use std::fmt;
 use std::sync::Arc;
 
+// Costs of visiting a node and of testing a primitive, relative to each other.
+const TRAVERSAL_COST: f64 = 1.0;
+const INTERSECTION_COST: f64 = 1.0;
+const SAH_BINS: usize = 16;
+const MAX_LEAF_SIZE: usize = 4;
+
 pub struct BvhNode {
-    left: Arc<dyn Hittable>,
-    right: Arc<dyn Hittable>,
     bbox: Aabb,
+    contents: Contents,
+}
+
+enum Contents {
+    Leaf(Vec<Arc<dyn Hittable>>),
+    Interior(Box<BvhNode>, Box<BvhNode>),
+}
+
+// A primitive with its bounds, cached while building.
+struct Item {
+    object: Arc<dyn Hittable>,
+    bbox: Aabb,
+    centroid: [f64; 3],
 }
 
 impl BvhNode {
+    // Splits at the median along a random axis, down to one primitive per leaf.
     pub fn new(objects: &mut [Arc<dyn Hittable>], time0: f64, time1: f64) -> Self {
-        let axis = rand::rng().random_range(0..3);
-        let comparator = |a: &Arc<dyn Hittable>, b: &Arc<dyn Hittable>| box_compare(a, b, axis);
-
-        let left: Arc<dyn Hittable>;
-        let right: Arc<dyn Hittable>;
-
-        let object_span = objects.len();
-
-        if object_span == 1 {
-            left = objects[0].clone();
-            right = objects[0].clone();
-        } else if object_span == 2 {
-            if comparator(&objects[0], &objects[1]) == Ordering::Less {
-                left = objects[0].clone();
-                right = objects[1].clone();
-            } else {
-                left = objects[1].clone();
-                right = objects[0].clone();
+        let axis = (3.0 * random_double()) as usize % 3;
+        let comparator =
+            |a: &Arc<dyn Hittable>, b: &Arc<dyn Hittable>| box_compare(a, b, axis, time0, time1);
+
+        if objects.len() == 1 {
+            let bbox = objects[0]
+                .bounding_box(time0, time1)
+                .expect("No bounding box in BvhNode constructor.");
+            return Self {
+                bbox,
+                contents: Contents::Leaf(vec![objects[0].clone()]),
+            };
+        }
+
+        objects.sort_by(comparator);
+        let mid = objects.len() / 2;
+        let (left_half, right_half) = objects.split_at_mut(mid);
+        let left = BvhNode::new(left_half, time0, time1);
+        let right = BvhNode::new(right_half, time0, time1);
+        Self::interior(left, right)
+    }
+
+    // Builds with the surface area heuristic, choosing among splits between bins of
+    // primitive centroids on every axis, and keeps up to a few primitives per leaf
+    // where splitting them wouldn't pay.
+    pub fn sah(objects: &mut [Arc<dyn Hittable>], time0: f64, time1: f64) -> Self {
+        assert!(!objects.is_empty(), "BvhNode needs at least one object");
+        let mut items: Vec<Item> = objects
+            .iter()
+            .map(|object| {
+                let bbox = object
+                    .bounding_box(time0, time1)
+                    .expect("No bounding box in BvhNode constructor.");
+                Item {
+                    object: object.clone(),
+                    bbox,
+                    centroid: [0, 1, 2].map(|a| 0.5 * (bbox.min[a] + bbox.max[a])),
+                }
+            })
+            .collect();
+        Self::build_sah(&mut items)
+    }
+
+    fn build_sah(items: &mut [Item]) -> Self {
+        let bbox = bounds(items.iter().map(|item| item.bbox));
+        let leaf = |items: &[Item]| Self {
+            bbox,
+            contents: Contents::Leaf(items.iter().map(|item| item.object.clone()).collect()),
+        };
+        if items.len() == 1 {
+            return leaf(items);
+        }
+
+        let centroid_min = [0, 1, 2].map(|a| {
+            items
+                .iter()
+                .map(|item| item.centroid[a])
+                .fold(f64::INFINITY, f64::min)
+        });
+        let centroid_max = [0, 1, 2].map(|a| {
+            items
+                .iter()
+                .map(|item| item.centroid[a])
+                .fold(f64::NEG_INFINITY, f64::max)
+        });
+        let bin_of = |item: &Item, axis: usize| {
+            let extent = centroid_max[axis] - centroid_min[axis];
+            let b =
+                (SAH_BINS as f64 * (item.centroid[axis] - centroid_min[axis]) / extent) as usize;
+            b.min(SAH_BINS - 1)
+        };
+
+        // The cheapest split as (cost, axis, number of bins on the left).
+        let mut best: Option<(f64, usize, usize)> = None;
+        for axis in 0..3 {
+            if centroid_max[axis] <= centroid_min[axis] {
+                continue;
             }
-        } else {
-            objects.sort_by(comparator);
-            let mid = object_span / 2;
-            let (left_half, right_half) = objects.split_at_mut(mid);
-            left = Arc::new(BvhNode::new(left_half, time0, time1));
-            right = Arc::new(BvhNode::new(right_half, time0, time1));
+            let mut counts = [0usize; SAH_BINS];
+            let mut boxes: [Option<Aabb>; SAH_BINS] = [None; SAH_BINS];
+            for item in items.iter() {
+                let b = bin_of(item, axis);
+                counts[b] += 1;
+                boxes[b] = Some(union(boxes[b], item.bbox));
+            }
+
+            // Sweep from the right to get the cost of each right-hand side, then from the
+            // left to total it up.
+            let mut right_area = [0.0; SAH_BINS];
+            let mut right_count = [0usize; SAH_BINS];
+            let (mut acc_box, mut acc_count) = (None, 0);
+            for split in (1..SAH_BINS).rev() {
+                acc_box = boxes[split].map_or(acc_box, |b| Some(union(acc_box, b)));
+                acc_count += counts[split];
+                right_area[split] = acc_box.map_or(0.0, |b: Aabb| b.surface_area());
+                right_count[split] = acc_count;
+            }
+            let (mut acc_box, mut acc_count) = (None, 0);
+            for split in 1..SAH_BINS {
+                acc_box = boxes[split - 1].map_or(acc_box, |b| Some(union(acc_box, b)));
+                acc_count += counts[split - 1];
+                if acc_count == 0 || right_count[split] == 0 {
+                    continue;
+                }
+                let left_area = acc_box.map_or(0.0, |b: Aabb| b.surface_area());
+                let cost =
+                    acc_count as f64 * left_area + right_count[split] as f64 * right_area[split];
+                if best.is_none_or(|(c, _, _)| cost < c) {
+                    best = Some((cost, axis, split));
+                }
+            }
+        }
+
+        let area = bbox.surface_area();
+        let leaf_cost = INTERSECTION_COST * items.len() as f64;
+        let mid = match best {
+            Some((cost, axis, split)) => {
+                let split_cost = if area > 0.0 {
+                    TRAVERSAL_COST + INTERSECTION_COST * cost / area
+                } else {
+                    TRAVERSAL_COST + leaf_cost
+                };
+                if items.len() <= MAX_LEAF_SIZE && leaf_cost <= split_cost {
+                    return leaf(items);
+                }
+                partition(items, |item| bin_of(item, axis) < split)
+            }
+            // Every centroid is in the same place, so no split tells them apart.
+            None if items.len() <= MAX_LEAF_SIZE => return leaf(items),
+            None => items.len() / 2,
+        };
+
+        let (left, right) = items.split_at_mut(mid);
+        Self::interior(Self::build_sah(left), Self::build_sah(right))
+    }
+
+    fn interior(left: BvhNode, right: BvhNode) -> Self {
+        Self {
+            bbox: surrounding_box(&left.bbox, &right.bbox),
+            contents: Contents::Interior(Box::new(left), Box::new(right)),
         }
+    }
 
-        let box_left = left.bounding_box(time0, time1);
-        let box_right = right.bounding_box(time0, time1);
+    // Measures the tree: its expected cost per ray under the surface area heuristic,
+    // depth and leaf sizes.
+    pub fn report(&self) -> BvhReport {
+        let mut report = BvhReport {
+            sah_cost: 0.0,
+            nodes: 0,
+            leaves: 0,
+            primitives: 0,
+            max_depth: 0,
+            min_leaf_size: usize::MAX,
+            max_leaf_size: 0,
+        };
+        self.measure(&mut report, 0, self.bbox.surface_area());
+        report
+    }
 
-        let bbox = if let (Some(left_box), Some(right_box)) = (box_left, box_right) {
-            surrounding_box(&left_box, &right_box)
+    fn measure(&self, report: &mut BvhReport, depth: usize, root_area: f64) {
+        let share = if root_area > 0.0 {
+            self.bbox.surface_area() / root_area
         } else {
-            panic!("No bounding box in BvhNode constructor.");
+            1.0
         };
+        report.nodes += 1;
+        report.max_depth = report.max_depth.max(depth);
+        match &self.contents {
+            Contents::Leaf(objects) => {
+                report.sah_cost += INTERSECTION_COST * share * objects.len() as f64;
+                report.leaves += 1;
+                report.primitives += objects.len();
+                report.min_leaf_size = report.min_leaf_size.min(objects.len());
+                report.max_leaf_size = report.max_leaf_size.max(objects.len());
+            }
+            Contents::Interior(left, right) => {
+                report.sah_cost += TRAVERSAL_COST * share;
+                left.measure(report, depth + 1, root_area);
+                right.measure(report, depth + 1, root_area);
+            }
+        }
+    }
 
-        Self { left, right, bbox }
+    // The primitives in the tree, e.g. to build another tree over them.
+    pub fn primitives(&self) -> Vec<Arc<dyn Hittable>> {
+        match &self.contents {
+            Contents::Leaf(objects) => objects.clone(),
+            Contents::Interior(left, right) => {
+                let mut objects = left.primitives();
+                objects.extend(right.primitives());
+                objects
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BvhReport {
+    pub sah_cost: f64,
+    pub nodes: usize,
+    pub leaves: usize,
+    pub primitives: usize,
+    pub max_depth: usize,
+    pub min_leaf_size: usize,
+    pub max_leaf_size: usize,
+}
+
+impl BvhReport {
+    pub fn mean_leaf_size(&self) -> f64 {
+        self.primitives as f64 / self.leaves as f64
+    }
+}
+
+impl fmt::Display for BvhReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "SAH cost {:.2}, {} nodes, depth {}, {} leaves of {}-{} primitives ({:.2} on average)",
+            self.sah_cost,
+            self.nodes,
+            self.max_depth,
+            self.leaves,
+            self.min_leaf_size,
+            self.max_leaf_size,
+            self.mean_leaf_size()
+        )
+    }
+}
+
+fn union(a: Option<Aabb>, b: Aabb) -> Aabb {
+    match a {
+        Some(a) => surrounding_box(&a, &b),
+        None => b,
+    }
+}
+
+fn bounds(boxes: impl Iterator<Item = Aabb>) -> Aabb {
+    boxes
+        .reduce(|a, b| surrounding_box(&a, &b))
+        .expect("No bounding box in BvhNode constructor.")
+}
+
+// Moves the items satisfying `left` to the front, returning how many there are.
+fn partition(items: &mut [Item], left: impl Fn(&Item) -> bool) -> usize {
+    let mut mid = 0;
+    for i in 0..items.len() {
+        if left(&items[i]) {
+            items.swap(i, mid);
+            mid += 1;
+        }
     }
+    mid
 }
 
-fn box_compare(a: &Arc<dyn Hittable>, b: &Arc<dyn Hittable>, axis: usize) -> Ordering {
-    let box_a = a.bounding_box(0.0, 0.0);
-    let box_b = b.bounding_box(0.0, 0.0);
+fn box_compare(
+    a: &Arc<dyn Hittable>,
+    b: &Arc<dyn Hittable>,
+    axis: usize,
+    time0: f64,
+    time1: f64,
+) -> Ordering {
+    let box_a = a.bounding_box(time0, time1);
+    let box_b = b.bounding_box(time0, time1);
 
     if let (Some(a), Some(b)) = (box_a, box_b) {
-        let a_min = match axis {
-            0 => a.min.x,
-            1 => a.min.y,
-            2 => a.min.z,
-            _ => unreachable!(),
-        };
-        let b_min = match axis {
-            0 => b.min.x,
-            1 => b.min.y,
-            2 => b.min.z,
-            _ => unreachable!(),
-        };
-        a_min.partial_cmp(&b_min).unwrap_or(Ordering::Equal)
+        a.min[axis]
+            .partial_cmp(&b.min[axis])
+            .unwrap_or(Ordering::Equal)
     } else {
         panic!("No bounding box in box_compare.");
     }
@@ -82,24 +311,90 @@ impl Hittable for BvhNode {
             return None;
         }
 
-        let hit_left = self.left.hit(r, t_min, t_max);
+        match &self.contents {
+            Contents::Leaf(objects) => {
+                let mut closest = t_max;
+                let mut hit = None;
+                for object in objects {
+                    if let Some(rec) = object.hit(r, t_min, closest) {
+                        closest = rec.t;
+                        hit = Some(rec);
+                    }
+                }
+                hit
+            }
+            Contents::Interior(left, right) => {
+                let hit_left = left.hit(r, t_min, t_max);
+                let t_max_for_right = hit_left.as_ref().map_or(t_max, |rec| rec.t);
+                right.hit(r, t_min, t_max_for_right).or(hit_left)
+            }
+        }
+    }
 
-        let t_max_for_right = if let Some(ref rec) = hit_left {
-            rec.t
-        } else {
-            t_max
-        };
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+}
 
-        let hit_right = self.right.hit(r, t_min, t_max_for_right);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::Sphere;
+    use crate::hittable_list::HittableList;
+    use crate::material::Lambertian;
+    use crate::rtweekend::{random_double_range, seed_random};
+    use crate::texture::SolidColor;
+    use crate::vec3::{Point3, Vec3};
 
-        if hit_right.is_some() {
-            hit_right
-        } else {
-            hit_left
+    fn random_spheres(n: usize) -> Vec<Arc<dyn Hittable>> {
+        let material = Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+            0.5, 0.5, 0.5,
+        ))));
+        (0..n)
+            .map(|_| {
+                Arc::new(Sphere::new(
+                    Vec3::random_range(-10.0, 10.0),
+                    random_double_range(0.05, 0.5),
+                    material.clone(),
+                )) as Arc<dyn Hittable>
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sah_finds_the_same_hits() {
+        seed_random(25);
+        let objects = random_spheres(500);
+        let bvh = BvhNode::sah(&mut objects.clone(), 0.0, 1.0);
+        let list = HittableList { objects };
+        for _ in 0..2000 {
+            let r = Ray::new(
+                Point3::new(0.0, 0.0, -20.0),
+                Vec3::random_range(-10.0, 10.0) - Point3::new(0.0, 0.0, -20.0),
+                0.0,
+            );
+            let expected = list.hit(&r, 0.001, f64::INFINITY).map(|rec| rec.t);
+            let found = bvh.hit(&r, 0.001, f64::INFINITY).map(|rec| rec.t);
+            assert_eq!(expected, found);
         }
     }
 
-    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<Aabb> {
-        Some(self.bbox)
+    #[test]
+    fn test_sah_beats_median_split() {
+        seed_random(25);
+        let objects = random_spheres(2000);
+        let sah = BvhNode::sah(&mut objects.clone(), 0.0, 1.0).report();
+        let median = BvhNode::new(&mut objects.clone(), 0.0, 1.0).report();
+        // The median split's random axes repeat once the generator is reseeded.
+        seed_random(7);
+        let first = BvhNode::new(&mut objects.clone(), 0.0, 1.0).report();
+        seed_random(7);
+        let again = BvhNode::new(&mut objects.clone(), 0.0, 1.0).report();
+        assert_eq!(first.to_string(), again.to_string());
+        assert_eq!(sah.primitives, 2000);
+        assert_eq!(median.primitives, 2000);
+        assert_eq!(median.max_leaf_size, 1);
+        assert!(sah.max_leaf_size <= MAX_LEAF_SIZE);
+        assert!(sah.sah_cost < median.sah_cost, "{} vs {}", sah, median);
     }
 }
@@ -1,5 +1,5 @@
 use crate::ray::Ray;
-use crate::rtweekend::degrees_to_radians;
+use crate::rtweekend::{degrees_to_radians, random_double};
 use crate::vec3::{Point3, Vec3};
 
 pub struct Camera {
@@ -10,6 +10,8 @@ pub struct Camera {
     u: Vec3,
     v: Vec3,
     lens_radius: f64,
+    time0: f64, // shutter open/close times
+    time1: f64,
 }
 
 impl Camera {
@@ -45,9 +47,17 @@ impl Camera {
             u,
             v,
             lens_radius,
+            time0: 0.0,
+            time1: 0.0,
         }
     }
 
+    pub fn with_shutter(mut self, time0: f64, time1: f64) -> Camera {
+        self.time0 = time0;
+        self.time1 = time1;
+        self
+    }
+
     pub fn get_ray(&self, s: f64, t: f64) -> Ray {
         let rd = self.lens_radius * Vec3::random_in_unit_disk();
         let offset = self.u * rd.x + self.v * rd.y;
@@ -55,6 +65,7 @@ impl Camera {
         Ray::new(
             self.origin + offset,
             self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
+            self.time0 + random_double() * (self.time1 - self.time0),
         )
     }
 }
@@ -8,6 +8,64 @@ pub struct Settings {
     pub samples_per_pixel: u32,
     pub max_depth: u32,
     pub output_filename: String,
+    #[serde(default = "default_scene")]
+    pub scene: String,
+    #[serde(default = "default_integrator")]
+    pub integrator: String,
+    // An equirectangular .hdr or .pfm that replaces the scene's background.
+    #[serde(default)]
+    pub environment_map: Option<String>,
+    #[serde(default)]
+    pub environment_rotation: f64, // degrees around the y axis
+    #[serde(default = "default_environment_intensity")]
+    pub environment_intensity: f64,
+    // "sky" swaps the scene's background for the physical sky below, "gradient" for
+    // the plain blue gradient.
+    #[serde(default)]
+    pub background: Option<String>,
+    #[serde(default = "default_sun_elevation")]
+    pub sun_elevation: f64, // degrees above the horizon
+    #[serde(default)]
+    pub sun_azimuth: f64, // degrees from +z toward +x
+    #[serde(default = "default_turbidity")]
+    pub turbidity: f64,
+    #[serde(default = "default_sky_intensity")]
+    pub sky_intensity: f64,
+    // Prints the SAH cost, depth and leaf sizes of the scene's BVH.
+    #[serde(default)]
+    pub bvh_report: bool,
+}
+
+fn default_scene() -> String {
+    "random".to_string()
+}
+
+fn default_integrator() -> String {
+    "path".to_string()
+}
+
+// The built-in settings, matching the config.toml that ships with the crate.
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            aspect_ratio: 16.0 / 9.0,
+            image_width: 384,
+            samples_per_pixel: 100,
+            max_depth: 50,
+            output_filename: "output.png".to_string(),
+            scene: default_scene(),
+            integrator: default_integrator(),
+            environment_map: None,
+            environment_rotation: 0.0,
+            environment_intensity: default_environment_intensity(),
+            background: None,
+            sun_elevation: default_sun_elevation(),
+            sun_azimuth: 0.0,
+            turbidity: default_turbidity(),
+            sky_intensity: default_sky_intensity(),
+            bvh_report: false,
+        }
+    }
 }
 
 impl Settings {
@@ -17,3 +75,32 @@ impl Settings {
         settings
     }
 }
+
+fn default_environment_intensity() -> f64 {
+    1.0
+}
+
+fn default_sun_elevation() -> f64 {
+    30.0
+}
+
+fn default_turbidity() -> f64 {
+    3.0
+}
+
+fn default_sky_intensity() -> f64 {
+    0.05
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_matches_shipped_config() {
+        let shipped: Settings =
+            toml::from_str(include_str!("../config.toml")).expect("config.toml should parse");
+        let default = Settings::default();
+        assert_eq!(format!("{:?}", shipped), format!("{:?}", default));
+    }
+}
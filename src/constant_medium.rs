@@ -0,0 +1,175 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::{Isotropic, Material};
+use crate::ray::Ray;
+use crate::rtweekend::random_double;
+use crate::texture::Texture;
+use crate::vec3::Vec3;
+use std::sync::Arc;
+
+// A volume of uniform density filling a closed, convex boundary.
+pub struct ConstantMedium {
+    boundary: Arc<dyn Hittable>,
+    phase_function: Arc<dyn Material>,
+    neg_inv_density: f64,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: Arc<dyn Hittable>, density: f64, albedo: Arc<dyn Texture>) -> Self {
+        Self {
+            boundary,
+            phase_function: Arc::new(Isotropic::new(albedo)),
+            neg_inv_density: -1.0 / density,
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut rec1 = self.boundary.hit(r, -f64::INFINITY, f64::INFINITY)?;
+        let mut rec2 = self.boundary.hit(r, rec1.t + 0.0001, f64::INFINITY)?;
+
+        if rec1.t < t_min {
+            rec1.t = t_min;
+        }
+        if rec2.t > t_max {
+            rec2.t = t_max;
+        }
+        if rec1.t >= rec2.t {
+            return None;
+        }
+        if rec1.t < 0.0 {
+            rec1.t = 0.0;
+        }
+
+        let ray_length = r.direction.length();
+        let distance_inside_boundary = (rec2.t - rec1.t) * ray_length;
+        let hit_distance = self.neg_inv_density * random_double().ln();
+        if hit_distance > distance_inside_boundary {
+            return None;
+        }
+
+        let t = rec1.t + hit_distance / ray_length;
+        Some(HitRecord {
+            p: r.at(t),
+            t,
+            u: 0.0,
+            v: 0.0,
+            normal: Vec3::new(1.0, 0.0, 0.0), // arbitrary
+            tangent: Vec3::new(0.0, 1.0, 0.0),
+            bitangent: Vec3::new(0.0, 0.0, 1.0),
+            front_face: true, // also arbitrary
+            mat_ptr: Arc::clone(&self.phase_function),
+        })
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
+        self.boundary.bounding_box(t0, t1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aabox::AaBox;
+    use crate::hittable::Sphere;
+    use crate::material::{Lambertian, ScatterRecord};
+    use crate::rtweekend::{PI, seed_random};
+    use crate::texture::SolidColor;
+    use crate::vec3::{Color, Point3};
+
+    fn gray() -> Arc<dyn Texture> {
+        Arc::new(SolidColor::from_rgb(0.5, 0.5, 0.5))
+    }
+
+    // The share of `n` rays that scatter in `medium`, checked against the chance
+    // 1 - exp(-density * distance) of scattering within `distance` to five sigma.
+    fn assert_scatters(medium: &ConstantMedium, r: &Ray, density: f64, distance: f64) {
+        let n = 20_000;
+        let hits = (0..n)
+            .filter(|_| medium.hit(r, 0.001, f64::INFINITY).is_some())
+            .count();
+        let p = 1.0 - (-density * distance).exp();
+        let sigma = (p * (1.0 - p) / n as f64).sqrt();
+        let share = hits as f64 / n as f64;
+        assert!((share - p).abs() < 5.0 * sigma, "{} vs {}", share, p);
+    }
+
+    #[test]
+    fn test_slab_scatters_by_its_optical_depth() {
+        seed_random(7);
+        let slab = Arc::new(AaBox::new(
+            Point3::new(-5.0, -5.0, 0.0),
+            Point3::new(5.0, 5.0, 2.0),
+            Arc::new(Lambertian::new(gray())),
+        ));
+        let medium = ConstantMedium::new(slab, 0.5, gray());
+        // A ray whose direction isn't of unit length, crossing the slab at a slant where
+        // its path through the thickness of 2 is 2 / 0.8 long.
+        let r = Ray::new(Point3::new(0.0, 0.0, -3.0), Vec3::new(0.0, 1.5, 2.0), 0.0);
+        assert_scatters(&medium, &r, 0.5, 2.0 / 0.8);
+
+        for _ in 0..100 {
+            if let Some(rec) = medium.hit(&r, 0.001, f64::INFINITY) {
+                assert!(rec.p.z >= 0.0 && rec.p.z <= 2.0, "{:?}", rec.p);
+            }
+        }
+        // Nothing scatters before the ray reaches the slab.
+        assert!(medium.hit(&r, 0.001, 1.0).is_none());
+    }
+
+    #[test]
+    fn test_rays_starting_inside_scatter_ahead() {
+        seed_random(7);
+        let ball = Arc::new(Sphere::new(
+            Point3::default(),
+            1.0,
+            Arc::new(Lambertian::new(gray())),
+        ));
+        let medium = ConstantMedium::new(ball, 1.5, gray());
+        let r = Ray::new(Point3::default(), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        // Only the radius ahead of the ray counts, not the part of the ball behind it.
+        assert_scatters(&medium, &r, 1.5, 1.0);
+        for _ in 0..100 {
+            if let Some(rec) = medium.hit(&r, 0.001, f64::INFINITY) {
+                assert!(rec.t >= 0.001 && rec.t <= 1.0, "{}", rec.t);
+            }
+        }
+    }
+
+    #[test]
+    fn test_isotropic_scatters_uniformly() {
+        seed_random(7);
+        let albedo = Color::new(0.2, 0.4, 0.6);
+        let fog = Isotropic::new(Arc::new(SolidColor::new(albedo)));
+        let r_in = Ray::new(Point3::default(), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let rec = HitRecord {
+            p: Point3::new(0.0, 0.0, 1.0),
+            normal: Vec3::new(1.0, 0.0, 0.0),
+            tangent: Vec3::new(0.0, 1.0, 0.0),
+            bitangent: Vec3::new(0.0, 0.0, 1.0),
+            t: 1.0,
+            u: 0.0,
+            v: 0.0,
+            mat_ptr: Arc::new(Isotropic::new(gray())),
+            front_face: true,
+        };
+        let mut srec = ScatterRecord::default();
+        assert!(fog.scatter(&r_in, &rec, &mut srec));
+        assert!(!srec.is_specular);
+        assert_eq!(srec.attenuation, albedo);
+
+        let pdf = srec.pdf_ptr.unwrap();
+        let n = 100_000;
+        let mut mean = Vec3::default();
+        for _ in 0..n {
+            let d = pdf.generate();
+            let scattered = Ray::new(rec.p, d, 0.0);
+            assert!((pdf.value(d) - 1.0 / (4.0 * PI)).abs() < 1e-12);
+            assert!((fog.scattering_pdf(&r_in, &rec, &scattered) - 1.0 / (4.0 * PI)).abs() < 1e-12);
+            mean += d / n as f64;
+        }
+        // Uniform directions average out, whichever way the light came from.
+        assert!(mean.length() < 5.0 / (n as f64).sqrt(), "{:?}", mean);
+    }
+}
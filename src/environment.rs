@@ -0,0 +1,332 @@
+use crate::background::Background;
+use crate::hittable::get_sphere_uv;
+use crate::ray::Ray;
+use crate::rtweekend::{PI, degrees_to_radians, random_double};
+use crate::vec3::{Color, Vec3};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum EnvironmentMapError {
+    Io(std::io::Error),
+    Image(image::ImageError),
+    Pfm(String),
+}
+
+impl fmt::Display for EnvironmentMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvironmentMapError::Io(e) => write!(f, "{}", e),
+            EnvironmentMapError::Image(e) => write!(f, "{}", e),
+            EnvironmentMapError::Pfm(message) => write!(f, "invalid PFM file: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for EnvironmentMapError {}
+
+// An equirectangular radiance map surrounding the scene, importance sampled by luminance.
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    texels: Vec<Color>, // linear radiance, row 0 looking straight up
+    rotation: f64,      // radians around the y axis
+    intensity: f64,
+    rows: Distribution1D,
+    columns: Vec<Distribution1D>,
+}
+
+impl EnvironmentMap {
+    // Loads a Radiance .hdr (or any format the image crate reads) or a .pfm file.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, EnvironmentMapError> {
+        let path = path.as_ref();
+        let is_pfm = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pfm"));
+
+        if is_pfm {
+            let bytes = fs::read(path).map_err(EnvironmentMapError::Io)?;
+            let (width, height, texels) = parse_pfm(&bytes)?;
+            return Ok(Self::from_texels(width, height, texels));
+        }
+
+        let img = image::open(path)
+            .map_err(EnvironmentMapError::Image)?
+            .to_rgb32f();
+        let texels = img
+            .pixels()
+            .map(|p| Color::new(p[0] as f64, p[1] as f64, p[2] as f64))
+            .collect();
+        Ok(Self::from_texels(
+            img.width() as usize,
+            img.height() as usize,
+            texels,
+        ))
+    }
+
+    pub fn from_texels(width: usize, height: usize, texels: Vec<Color>) -> Self {
+        assert_eq!(
+            texels.len(),
+            width * height,
+            "texel count must be width * height"
+        );
+
+        // Weight each texel by the solid angle it covers, which shrinks toward the poles.
+        let weights: Vec<Vec<f64>> = (0..height)
+            .map(|y| {
+                let sin_theta = (PI * (y as f64 + 0.5) / height as f64).sin();
+                (0..width)
+                    .map(|x| luminance(texels[y * width + x]) * sin_theta)
+                    .collect()
+            })
+            .collect();
+        let columns: Vec<Distribution1D> = weights.iter().map(|w| Distribution1D::new(w)).collect();
+        let row_weights: Vec<f64> = columns.iter().map(|c| c.total).collect();
+
+        Self {
+            width,
+            height,
+            texels,
+            rotation: 0.0,
+            intensity: 1.0,
+            rows: Distribution1D::new(&row_weights),
+            columns,
+        }
+    }
+
+    pub fn with_rotation(mut self, degrees: f64) -> Self {
+        self.rotation = degrees_to_radians(degrees);
+        self
+    }
+
+    pub fn with_intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    fn rotate(&self, d: Vec3, angle: f64) -> Vec3 {
+        let (sin, cos) = angle.sin_cos();
+        Vec3::new(cos * d.x + sin * d.z, d.y, -sin * d.x + cos * d.z)
+    }
+
+    // Texel coordinates of a world-space direction.
+    fn texel_of(&self, direction: &Vec3) -> (usize, usize) {
+        let d = self.rotate(direction.unit_vector(), -self.rotation);
+        let (u, v) = get_sphere_uv(&d);
+        let x = ((u * self.width as f64) as usize).min(self.width - 1);
+        let y = (((1.0 - v) * self.height as f64) as usize).min(self.height - 1);
+        (x, y)
+    }
+}
+
+impl Background for EnvironmentMap {
+    fn value(&self, r: &Ray) -> Color {
+        let (x, y) = self.texel_of(&r.direction);
+        self.intensity * self.texels[y * self.width + x]
+    }
+
+    fn random(&self) -> Option<Vec3> {
+        if self.rows.total <= 0.0 {
+            return None;
+        }
+        let y = self.rows.sample(random_double());
+        let x = self.columns[y].sample(random_double());
+
+        // Invert get_sphere_uv at a uniformly jittered point inside the texel.
+        let u = (x as f64 + random_double()) / self.width as f64;
+        let v = 1.0 - (y as f64 + random_double()) / self.height as f64;
+        let phi = (1.0 - u) * 2.0 * PI - PI;
+        let cos_theta = -(v * PI).cos();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let d = Vec3::new(phi.cos() * sin_theta, cos_theta, phi.sin() * sin_theta);
+
+        Some(self.rotate(d, self.rotation))
+    }
+
+    fn pdf_value(&self, direction: &Vec3) -> f64 {
+        if self.rows.total <= 0.0 {
+            return 0.0;
+        }
+        let d = direction.unit_vector();
+        let sin_theta = (1.0 - d.y * d.y).max(0.0).sqrt();
+        if sin_theta == 0.0 {
+            return 0.0;
+        }
+
+        let (x, y) = self.texel_of(direction);
+        // Density over the unit (u, v) square, converted to solid angle.
+        let pdf_uv = self.rows.probability(y)
+            * self.columns[y].probability(x)
+            * (self.width * self.height) as f64;
+        pdf_uv / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+pub fn luminance(c: Color) -> f64 {
+    0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+}
+
+// A piecewise-constant distribution over bins, sampled by inverting its CDF.
+struct Distribution1D {
+    cdf: Vec<f64>,
+    total: f64,
+}
+
+impl Distribution1D {
+    fn new(weights: &[f64]) -> Self {
+        let mut cdf = Vec::with_capacity(weights.len() + 1);
+        cdf.push(0.0);
+        for w in weights {
+            cdf.push(cdf.last().unwrap() + w.max(0.0));
+        }
+        let total = *cdf.last().unwrap();
+        Self { cdf, total }
+    }
+
+    fn probability(&self, bin: usize) -> f64 {
+        if self.total <= 0.0 {
+            return 0.0;
+        }
+        (self.cdf[bin + 1] - self.cdf[bin]) / self.total
+    }
+
+    fn sample(&self, xi: f64) -> usize {
+        let target = xi * self.total;
+        let bins = self.cdf.len() - 1;
+        // The first bin whose upper edge exceeds the target.
+        self.cdf[1..]
+            .partition_point(|&c| c <= target)
+            .min(bins - 1)
+    }
+}
+
+// Parses a Portable Float Map. Rows are stored bottom to top.
+fn parse_pfm(bytes: &[u8]) -> Result<(usize, usize, Vec<Color>), EnvironmentMapError> {
+    let error = |message: &str| EnvironmentMapError::Pfm(message.to_string());
+
+    // The header is three whitespace separated tokens after the magic, then one byte.
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while tokens.len() < 4 {
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        let start = pos;
+        while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if start == pos {
+            return Err(error("truncated header"));
+        }
+        tokens.push(String::from_utf8_lossy(&bytes[start..pos]).into_owned());
+    }
+    pos += 1;
+
+    let channels = match tokens[0].as_str() {
+        "PF" => 3,
+        "Pf" => 1,
+        _ => return Err(error("missing PF or Pf magic")),
+    };
+    let width: usize = tokens[1].parse().map_err(|_| error("invalid width"))?;
+    let height: usize = tokens[2].parse().map_err(|_| error("invalid height"))?;
+    let scale: f64 = tokens[3].parse().map_err(|_| error("invalid scale"))?;
+    let little_endian = scale < 0.0;
+
+    let count = width * height * channels;
+    let data = bytes
+        .get(pos..pos + 4 * count)
+        .ok_or_else(|| error("truncated data"))?;
+    let floats: Vec<f64> = data
+        .chunks_exact(4)
+        .map(|b| {
+            let b = [b[0], b[1], b[2], b[3]];
+            if little_endian {
+                f32::from_le_bytes(b) as f64
+            } else {
+                f32::from_be_bytes(b) as f64
+            }
+        })
+        .collect();
+
+    let mut texels = Vec::with_capacity(width * height);
+    for y in (0..height).rev() {
+        for x in 0..width {
+            let i = (y * width + x) * channels;
+            texels.push(if channels == 3 {
+                Color::new(floats[i], floats[i + 1], floats[i + 2])
+            } else {
+                Color::new(floats[i], floats[i], floats[i])
+            });
+        }
+    }
+    Ok((width, height, texels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rtweekend::seed_random;
+
+    // A dim map with a single bright texel.
+    fn spot_map() -> EnvironmentMap {
+        let (width, height) = (16, 8);
+        let mut texels = vec![Color::new(0.1, 0.1, 0.1); width * height];
+        texels[3 * width + 5] = Color::new(100.0, 100.0, 100.0);
+        EnvironmentMap::from_texels(width, height, texels).with_rotation(40.0)
+    }
+
+    #[test]
+    fn test_pdf_integrates_to_one() {
+        seed_random(13);
+        let env = spot_map();
+        let n = 200_000;
+        let sum: f64 = (0..n)
+            .map(|_| env.pdf_value(&Vec3::random_unit_vector()))
+            .sum();
+        assert!((sum * 4.0 * PI / n as f64 - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_samples_favor_bright_texels() {
+        seed_random(13);
+        let env = spot_map();
+        // Texels are drawn by luminance times the sine of their row's polar angle.
+        let weight = |y: usize, lum: f64| lum * (PI * (y as f64 + 0.5) / 8.0).sin();
+        let total: f64 =
+            (0..8).map(|y| 16.0 * weight(y, 0.1)).sum::<f64>() - weight(3, 0.1) + weight(3, 100.0);
+        let p = weight(3, 100.0) / total;
+
+        let n = 20_000;
+        let bright = (0..n)
+            .filter(|_| {
+                let d = env.random().unwrap();
+                let r = Ray::new(Vec3::default(), d, 0.0);
+                env.value(&r).x > 1.0 && env.pdf_value(&d) > 0.0
+            })
+            .count();
+        let expected = n as f64 * p;
+        let sigma = (n as f64 * p * (1.0 - p)).sqrt();
+        assert!(
+            (bright as f64 - expected).abs() < 5.0 * sigma,
+            "{} of {} samples hit the bright texel, expected {:.0}",
+            bright,
+            n,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_pfm() {
+        let mut bytes = b"PF\n2 1\n-1.0\n".to_vec();
+        for v in [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0] {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        let (width, height, texels) = parse_pfm(&bytes).unwrap();
+        assert_eq!((width, height), (2, 1));
+        assert_eq!(texels[1], Color::new(4.0, 5.0, 6.0));
+
+        assert!(parse_pfm(b"P6\n2 1\n-1.0\n").is_err());
+        assert!(parse_pfm(b"PF\n2 1\n-1.0\n\0\0").is_err());
+    }
+}
@@ -1,13 +1,18 @@
 use crate::aabb::Aabb;
 use crate::material::Material;
+use crate::onb::Onb;
 use crate::ray::Ray;
+use crate::rtweekend::random_double;
 use crate::vec3::{Point3, Vec3};
 use std::f64::consts::PI;
 use std::sync::Arc;
 
+#[derive(Clone)]
 pub struct HitRecord {
     pub p: Point3,
     pub normal: Vec3,
+    pub tangent: Vec3,   // dp/du, how far and which way p moves per unit of u
+    pub bitangent: Vec3, // dp/dv
     pub t: f64,
     pub u: f64,
     pub v: f64,
@@ -16,7 +21,7 @@ pub struct HitRecord {
 }
 
 impl HitRecord {
-    fn set_face_normal(&mut self, r: &Ray, outward_normal: Vec3) {
+    pub fn set_face_normal(&mut self, r: &Ray, outward_normal: Vec3) {
         self.front_face = r.direction.dot(outward_normal) < 0.0;
         self.normal = if self.front_face {
             outward_normal
@@ -29,6 +34,16 @@ impl HitRecord {
 pub trait Hittable: Send + Sync {
     fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
     fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb>;
+
+    // Density, with respect to solid angle at `origin`, of sampling `direction` via `random`.
+    fn pdf_value(&self, _origin: &Point3, _direction: &Vec3) -> f64 {
+        0.0
+    }
+
+    // A direction from `origin` toward a random point of this object.
+    fn random(&self, _origin: &Point3) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
 }
 
 pub struct Sphere {
@@ -63,12 +78,16 @@ impl Hittable for Sphere {
                 let t = temp;
                 let p = r.at(t);
                 let (u, v) = get_sphere_uv(&((p - self.center) / self.radius));
+                let (tangent, bitangent) =
+                    get_sphere_tangents(&((p - self.center) / self.radius), self.radius);
                 let mut rec = HitRecord {
                     p,
                     t,
                     u,
                     v,
                     normal: Vec3::new(0.0, 0.0, 0.0),
+                    tangent,
+                    bitangent,
                     front_face: false,
                     mat_ptr: Arc::clone(&self.mat_ptr), // Assign the sphere's material
                 };
@@ -82,12 +101,16 @@ impl Hittable for Sphere {
                 let t = temp;
                 let p = r.at(t);
                 let (u, v) = get_sphere_uv(&((p - self.center) / self.radius));
+                let (tangent, bitangent) =
+                    get_sphere_tangents(&((p - self.center) / self.radius), self.radius);
                 let mut rec = HitRecord {
                     p,
                     t,
                     u,
                     v,
                     normal: Vec3::new(0.0, 0.0, 0.0),
+                    tangent,
+                    bitangent,
                     front_face: false,
                     mat_ptr: Arc::clone(&self.mat_ptr), // Assign the sphere's material
                 };
@@ -106,6 +129,44 @@ impl Hittable for Sphere {
         );
         Some(output_box)
     }
+
+    // Zero from inside the sphere, where there is no cone of directions to sample.
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        let distance_squared = (self.center - *origin).length_squared();
+        if distance_squared <= self.radius * self.radius
+            || self
+                .hit(&Ray::new(*origin, *direction, 0.0), 0.001, f64::INFINITY)
+                .is_none()
+        {
+            return 0.0;
+        }
+
+        let cos_theta_max = (1.0 - self.radius * self.radius / distance_squared).sqrt();
+        let solid_angle = 2.0 * PI * (1.0 - cos_theta_max);
+        1.0 / solid_angle
+    }
+
+    fn random(&self, origin: &Point3) -> Vec3 {
+        let direction = self.center - *origin;
+        if direction.length_squared() <= self.radius * self.radius {
+            return Vec3::random_unit_vector();
+        }
+        let uvw = Onb::build_from_w(direction);
+        uvw.local_vec(random_to_sphere(self.radius, direction.length_squared()))
+    }
+}
+
+// A direction inside the cone subtended by a sphere at squared distance `distance_squared`.
+fn random_to_sphere(radius: f64, distance_squared: f64) -> Vec3 {
+    let r1 = random_double();
+    let r2 = random_double();
+    let z = 1.0 + r2 * ((1.0 - radius * radius / distance_squared).sqrt() - 1.0);
+
+    let phi = 2.0 * PI * r1;
+    let x = phi.cos() * (1.0 - z * z).sqrt();
+    let y = phi.sin() * (1.0 - z * z).sqrt();
+
+    Vec3::new(x, y, z)
 }
 
 pub fn get_sphere_uv(p: &Point3) -> (f64, f64) {
@@ -122,3 +183,72 @@ pub fn get_sphere_uv(p: &Point3) -> (f64, f64) {
     let v = (theta + PI / 2.0) / PI;
     (u, v)
 }
+
+// dp/du and dp/dv for the `get_sphere_uv` coordinates of the point `p` on the unit sphere,
+// on a sphere of `radius`.
+pub fn get_sphere_tangents(p: &Point3, radius: f64) -> (Vec3, Vec3) {
+    let tangent = Vec3::new(p.z, 0.0, -p.x);
+    if tangent.length_squared() < 1e-12 {
+        // u is degenerate at the poles, where any frame will do.
+        let uvw = Onb::build_from_w(*p);
+        return (uvw.u(), PI * radius * uvw.v());
+    }
+    // u goes once around the circle of radius r sin(theta) and v half way around a great
+    // circle.
+    (
+        2.0 * PI * radius * tangent,
+        PI * radius * p.cross(tangent.unit_vector()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::texture::SolidColor;
+
+    #[test]
+    fn test_sphere_tangents_follow_uv() {
+        let sphere = Sphere::new(
+            Point3::new(1.0, 2.0, 3.0),
+            2.0,
+            Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+                0.5, 0.5, 0.5,
+            )))),
+        );
+        let r = Ray::new(Point3::new(4.0, 3.0, 0.0), Vec3::new(-1.0, -0.2, 1.0), 0.0);
+        let rec = sphere.hit(&r, 0.001, f64::INFINITY).unwrap();
+        assert!(rec.tangent.dot(rec.normal).abs() < 1e-9);
+        assert!(rec.bitangent.dot(rec.normal).abs() < 1e-9);
+
+        // Stepping by a small multiple of each tangent moves u or v by that much.
+        let uv_at = |p: Point3| get_sphere_uv(&(p - sphere.center).unit_vector());
+        let (u1, _) = uv_at(rec.p + 1e-5 * rec.tangent);
+        let (_, v1) = uv_at(rec.p + 1e-5 * rec.bitangent);
+        assert!((u1 - rec.u - 1e-5).abs() < 1e-7, "{}", u1 - rec.u);
+        assert!((v1 - rec.v - 1e-5).abs() < 1e-7, "{}", v1 - rec.v);
+    }
+
+    #[test]
+    fn test_sphere_pdf_from_inside_is_zero() {
+        let sphere = Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            1.0,
+            Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+                0.5, 0.5, 0.5,
+            )))),
+        );
+        for origin in [Point3::new(0.2, 0.1, 0.0), Point3::new(1.0, 0.0, 0.0)] {
+            let direction = sphere.random(&origin);
+            assert!(direction.length() > 0.0 && direction.x.is_finite());
+            assert_eq!(sphere.pdf_value(&origin, &Vec3::new(1.0, 0.0, 0.0)), 0.0);
+            assert_eq!(sphere.pdf_value(&origin, &direction), 0.0);
+        }
+
+        // From outside it is one over the solid angle of the cone.
+        let origin = Point3::new(0.0, 0.0, -2.0);
+        let pdf = sphere.pdf_value(&origin, &Vec3::new(0.0, 0.0, 1.0));
+        let solid_angle = 2.0 * PI * (1.0 - 0.75f64.sqrt());
+        assert!((pdf - 1.0 / solid_angle).abs() < 1e-9);
+    }
+}
@@ -1,6 +1,8 @@
 use crate::aabb::{Aabb, surrounding_box};
 use crate::hittable::{HitRecord, Hittable};
 use crate::ray::Ray;
+use crate::rtweekend::random_double;
+use crate::vec3::{Point3, Vec3};
 use std::sync::Arc;
 
 pub struct HittableList {
@@ -70,4 +72,17 @@ impl Hittable for HittableList {
         }
         output_box
     }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        let weight = 1.0 / self.objects.len() as f64;
+        self.objects
+            .iter()
+            .map(|object| weight * object.pdf_value(origin, direction))
+            .sum()
+    }
+
+    fn random(&self, origin: &Point3) -> Vec3 {
+        let index = (random_double() * self.objects.len() as f64) as usize;
+        self.objects[index.min(self.objects.len() - 1)].random(origin)
+    }
 }
@@ -0,0 +1,805 @@
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::ScatterRecord;
+use crate::pdf::{BackgroundPdf, HittablePdf, MixturePdf, Pdf};
+use crate::ray::Ray;
+use crate::rtweekend::random_double;
+use crate::scenes::Scene;
+use crate::spectrum::{SampledSpectrum, SampledWavelengths};
+use crate::vec3::{Color, Vec3};
+use std::sync::Arc;
+
+// Scattering events after which a random walk is given up as lost inside the medium.
+const MAX_RANDOM_WALK: u32 = 1024;
+
+// Computes the radiance arriving along a camera ray.
+pub trait Integrator: Send + Sync {
+    fn ray_color(&self, r: Ray, scene: &Scene) -> Color;
+}
+
+pub fn by_name(name: &str, max_depth: u32) -> Option<Arc<dyn Integrator>> {
+    match name {
+        "path" => Some(Arc::new(PathTracer::new(max_depth))),
+        "mis" => Some(Arc::new(MisPathTracer::new(max_depth))),
+        "spectral" => Some(Arc::new(SpectralPathTracer::new(max_depth))),
+        "ambient_occlusion" => Some(Arc::new(AmbientOcclusion::new(f64::INFINITY))),
+        "normals" => Some(Arc::new(Normals)),
+        _ => None,
+    }
+}
+
+// Unidirectional path tracing that follows one scattered ray at every bounce, drawn from
+// a mix of the material, the lights and the background.
+pub struct PathTracer {
+    max_depth: u32,
+}
+
+impl PathTracer {
+    pub fn new(max_depth: u32) -> Self {
+        Self { max_depth }
+    }
+}
+
+impl Integrator for PathTracer {
+    fn ray_color(&self, mut r: Ray, scene: &Scene) -> Color {
+        let samples_background = scene.background.random().is_some();
+        let mut color = Color::default();
+        let mut attenuation = Color::new(1.0, 1.0, 1.0);
+        let mut current_depth = self.max_depth;
+
+        while current_depth > 0 {
+            let (hit, medium_weight) = next_hit(&mut r, scene);
+            attenuation = attenuation * medium_weight;
+            let Some(rec) = hit else {
+                return color + attenuation * scene.background.value(&r);
+            };
+
+            let emitted = rec.mat_ptr.emitted(rec.u, rec.v, &rec.p);
+            color += attenuation * emitted;
+
+            let mut srec = ScatterRecord::default();
+            if !rec.mat_ptr.scatter(&r, &rec, &mut srec) {
+                return color;
+            }
+
+            if srec.is_specular {
+                attenuation = attenuation * srec.attenuation;
+                r = continue_path(&r, &rec, srec.specular_ray);
+                current_depth -= 1;
+                continue;
+            }
+
+            let Some(material_pdf) = srec.pdf_ptr.as_deref() else {
+                return color;
+            };
+
+            color += attenuation * sample_delta_lights(&r, &rec, &srec, scene);
+
+            // Send half of the samples toward the lights when the scene has any, and half
+            // of the rest toward the background when it can be sampled.
+            let background_pdf = BackgroundPdf::new(scene.background.as_ref());
+            let with_background = MixturePdf::new(&background_pdf, material_pdf);
+            let pdf: &dyn Pdf = if samples_background {
+                &with_background
+            } else {
+                material_pdf
+            };
+            let light_pdf = HittablePdf::new(&scene.lights, rec.p);
+            let with_lights = MixturePdf::new(&light_pdf, pdf);
+            let pdf: &dyn Pdf = if scene.lights.objects.is_empty() {
+                pdf
+            } else {
+                &with_lights
+            };
+
+            let scattered = Ray::new(rec.p, pdf.generate(), r.time);
+            let pdf_val = pdf.value(scattered.direction);
+            if pdf_val <= 0.0 {
+                return color;
+            }
+
+            attenuation = attenuation * rec.mat_ptr.eval(&r, &rec, &srec, &scattered) / pdf_val;
+            r = continue_path(&r, &rec, scattered);
+            current_depth -= 1;
+        }
+
+        color // If max_depth is reached, no more light is gathered.
+    }
+}
+
+// Path tracing with next-event estimation: every non-specular bounce also sends shadow
+// rays toward the lights and the background, and each strategy is weighted against BSDF
+// sampling by the power heuristic.
+pub struct MisPathTracer {
+    max_depth: u32,
+}
+
+impl MisPathTracer {
+    pub fn new(max_depth: u32) -> Self {
+        Self { max_depth }
+    }
+}
+
+impl Integrator for MisPathTracer {
+    fn ray_color(&self, mut r: Ray, scene: &Scene) -> Color {
+        let mut color = Color::default();
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        // Density of the BSDF sample that produced `r`; None for camera and specular rays.
+        let mut bsdf_pdf: Option<f64> = None;
+        let mut current_depth = self.max_depth;
+
+        while current_depth > 0 {
+            let (hit, medium_weight) = next_hit(&mut r, scene);
+            throughput = throughput * medium_weight;
+            let Some(rec) = hit else {
+                let weight = match bsdf_pdf {
+                    Some(pdf) => power_heuristic(pdf, scene.background.pdf_value(&r.direction)),
+                    None => 1.0,
+                };
+                return color + throughput * scene.background.value(&r) * weight;
+            };
+
+            let emitted = rec.mat_ptr.emitted(rec.u, rec.v, &rec.p);
+            let weight = match bsdf_pdf {
+                Some(pdf) if !scene.lights.objects.is_empty() => {
+                    power_heuristic(pdf, scene.lights.pdf_value(&r.origin, &r.direction))
+                }
+                _ => 1.0,
+            };
+            color += throughput * emitted * weight;
+
+            let mut srec = ScatterRecord::default();
+            if !rec.mat_ptr.scatter(&r, &rec, &mut srec) {
+                return color;
+            }
+
+            if srec.is_specular {
+                throughput = throughput * srec.attenuation;
+                r = continue_path(&r, &rec, srec.specular_ray);
+                bsdf_pdf = None;
+                current_depth -= 1;
+                continue;
+            }
+
+            let Some(material_pdf) = srec.pdf_ptr.as_deref() else {
+                return color;
+            };
+
+            if !scene.lights.objects.is_empty() {
+                color += throughput * sample_light(&r, &rec, &srec, material_pdf, scene);
+            }
+            color += throughput * sample_background(&r, &rec, &srec, material_pdf, scene);
+            color += throughput * sample_delta_lights(&r, &rec, &srec, scene);
+
+            let scattered = Ray::new(rec.p, material_pdf.generate(), r.time);
+            let pdf_val = material_pdf.value(scattered.direction);
+            if pdf_val <= 0.0 {
+                return color;
+            }
+
+            throughput = throughput * rec.mat_ptr.eval(&r, &rec, &srec, &scattered) / pdf_val;
+            r = continue_path(&r, &rec, scattered);
+            bsdf_pdf = Some(pdf_val);
+            current_depth -= 1;
+        }
+
+        color // If max_depth is reached, no more light is gathered.
+    }
+}
+
+// The MIS path tracer carrying hero wavelengths instead of RGB. Materials and lights
+// still speak RGB, which is upsampled to spectra at every bounce, and the radiance is
+// converted to RGB through CIE XYZ once the path ends.
+pub struct SpectralPathTracer {
+    max_depth: u32,
+}
+
+impl SpectralPathTracer {
+    pub fn new(max_depth: u32) -> Self {
+        Self { max_depth }
+    }
+}
+
+impl Integrator for SpectralPathTracer {
+    fn ray_color(&self, mut r: Ray, scene: &Scene) -> Color {
+        let mut wavelengths = SampledWavelengths::sample_uniform(random_double());
+        let spectrum = |rgb: Color, wavelengths: &SampledWavelengths| {
+            SampledSpectrum::from_rgb(rgb, wavelengths)
+        };
+        let reflectance = |rgb: Color, wavelengths: &SampledWavelengths| {
+            SampledSpectrum::from_reflectance(rgb, wavelengths)
+        };
+
+        let mut radiance = SampledSpectrum::default();
+        let mut throughput = SampledSpectrum::splat(1.0);
+        let mut bsdf_pdf: Option<f64> = None;
+        let mut current_depth = self.max_depth;
+        r.wavelength = Some(wavelengths.hero());
+
+        while current_depth > 0 {
+            let (hit, medium_weight) = next_hit(&mut r, scene);
+            throughput *= reflectance(medium_weight, &wavelengths);
+            let Some(rec) = hit else {
+                let weight = match bsdf_pdf {
+                    Some(pdf) => power_heuristic(pdf, scene.background.pdf_value(&r.direction)),
+                    None => 1.0,
+                };
+                radiance +=
+                    throughput * spectrum(scene.background.value(&r) * weight, &wavelengths);
+                break;
+            };
+
+            let emitted = rec.mat_ptr.emitted(rec.u, rec.v, &rec.p);
+            let weight = match bsdf_pdf {
+                Some(pdf) if !scene.lights.objects.is_empty() => {
+                    power_heuristic(pdf, scene.lights.pdf_value(&r.origin, &r.direction))
+                }
+                _ => 1.0,
+            };
+            radiance += throughput * spectrum(emitted * weight, &wavelengths);
+
+            let mut srec = ScatterRecord::default();
+            if !rec.mat_ptr.scatter(&r, &rec, &mut srec) {
+                break;
+            }
+            if srec.wavelength_dependent {
+                wavelengths.terminate_secondary();
+            }
+
+            if srec.is_specular {
+                throughput *= reflectance(srec.attenuation, &wavelengths);
+                r = continue_path(&r, &rec, srec.specular_ray);
+                bsdf_pdf = None;
+                current_depth -= 1;
+                continue;
+            }
+
+            let Some(material_pdf) = srec.pdf_ptr.as_deref() else {
+                break;
+            };
+
+            let mut direct = sample_background(&r, &rec, &srec, material_pdf, scene)
+                + sample_delta_lights(&r, &rec, &srec, scene);
+            if !scene.lights.objects.is_empty() {
+                direct += sample_light(&r, &rec, &srec, material_pdf, scene);
+            }
+            radiance += throughput * spectrum(direct, &wavelengths);
+
+            let scattered = Ray::new(rec.p, material_pdf.generate(), r.time);
+            let pdf_val = material_pdf.value(scattered.direction);
+            if pdf_val <= 0.0 {
+                break;
+            }
+
+            throughput *= reflectance(
+                rec.mat_ptr.eval(&r, &rec, &srec, &scattered) / pdf_val,
+                &wavelengths,
+            );
+            r = continue_path(&r, &rec, scattered);
+            bsdf_pdf = Some(pdf_val);
+            current_depth -= 1;
+        }
+
+        radiance.to_rgb(&wavelengths)
+    }
+}
+
+// The first surface `r` really hits, and the weight of the media it passes through on
+// the way. Surfaces of a nested dielectric inside a higher priority one are stepped over,
+// with `r.media` tracking the crossings, and inside a scattering medium the path takes a
+// random walk, leaving `r` as the last step of it.
+fn next_hit(r: &mut Ray, scene: &Scene) -> (Option<HitRecord>, Color) {
+    let mut weight = Color::new(1.0, 1.0, 1.0);
+    // A random walk samples its free flights by the extinction of one channel, chosen
+    // up front, and is weighted against having used any of the three: `walk` holds that
+    // channel, the walk's contribution and its density under each channel.
+    let mut walk: Option<(usize, Color, Color)> = None;
+    let walk_weight = |walk: Option<(usize, Color, Color)>| match walk {
+        Some((_, f, pdf)) => f / ((pdf.x + pdf.y + pdf.z) / 3.0),
+        None => Color::new(1.0, 1.0, 1.0),
+    };
+    let mut t_start = 0.0;
+    let mut steps = 0;
+    loop {
+        let hit = scene.hit(r, t_start + 0.001, f64::INFINITY);
+        let t = hit.as_ref().map_or(f64::INFINITY, |rec| rec.t);
+        let length = r.direction.length();
+        let distance = (t - t_start) * length;
+
+        match r
+            .media
+            .current()
+            .filter(|m| m.scattering != Color::default())
+        {
+            Some(medium) => {
+                let (channel, f, pdf) = walk.get_or_insert_with(|| {
+                    let channel = (3.0 * random_double()) as usize % 3;
+                    (
+                        channel,
+                        Color::new(1.0, 1.0, 1.0),
+                        Color::new(1.0, 1.0, 1.0),
+                    )
+                });
+                let sigma_t = medium.extinction();
+                let flight = -(1.0 - random_double()).ln() / sigma_t[*channel];
+                if flight < distance {
+                    steps += 1;
+                    if steps > MAX_RANDOM_WALK {
+                        return (None, Color::default());
+                    }
+                    let tr = r.media.transmittance(flight);
+                    *f = *f * medium.scattering * tr;
+                    *pdf = *pdf * sigma_t * tr;
+                    // Only the ratio matters, so keep the numbers in range.
+                    let scale = pdf[*channel];
+                    *f /= scale;
+                    *pdf /= scale;
+
+                    let mut next = Ray::new(
+                        r.at(t_start + flight / length),
+                        Vec3::random_unit_vector(),
+                        r.time,
+                    );
+                    next.wavelength = r.wavelength;
+                    next.media = r.media;
+                    *r = next;
+                    t_start = 0.0;
+                    continue;
+                }
+                let tr = r.media.transmittance(distance);
+                *f = *f * tr;
+                *pdf = *pdf * tr;
+            }
+            None => weight = weight * r.media.transmittance(distance),
+        }
+
+        let Some(rec) = hit else {
+            return (None, weight * walk_weight(walk));
+        };
+        match rec.mat_ptr.medium() {
+            Some(medium) => {
+                if r.media.is_real_interface(&medium, rec.front_face) {
+                    return (Some(rec), weight * walk_weight(walk));
+                }
+                r.media.cross(medium, rec.front_face);
+                t_start = rec.t;
+            }
+            None => return (Some(rec), weight * walk_weight(walk)),
+        }
+    }
+}
+
+// Carries the path state of `r` over to the ray `next` leaving the surface in `rec`,
+// entering or leaving its medium if `next` was transmitted.
+fn continue_path(r: &Ray, rec: &HitRecord, mut next: Ray) -> Ray {
+    next.wavelength = r.wavelength;
+    next.media = r.media;
+    if let Some(medium) = rec.mat_ptr.medium()
+        && next.direction.dot(rec.normal) < 0.0
+    {
+        next.media.cross(medium, rec.front_face);
+    }
+    next
+}
+
+// One MIS-weighted light sample for the surface point in `rec`.
+fn sample_light(
+    r: &Ray,
+    rec: &HitRecord,
+    srec: &ScatterRecord,
+    material_pdf: &dyn Pdf,
+    scene: &Scene,
+) -> Color {
+    let shadow_ray = Ray::new(rec.p, scene.lights.random(&rec.p), r.time);
+    let light_pdf = scene
+        .lights
+        .pdf_value(&shadow_ray.origin, &shadow_ray.direction);
+    if light_pdf <= 0.0 {
+        return Color::default();
+    }
+
+    let f = rec.mat_ptr.eval(r, rec, srec, &shadow_ray);
+    if f == Color::default() {
+        return Color::default();
+    }
+
+    // Whatever the shadow ray reaches first is what the point sees in that direction.
+    let Some(light_rec) = scene.hit(&shadow_ray, 0.001, f64::INFINITY) else {
+        return Color::default();
+    };
+    let emitted = light_rec
+        .mat_ptr
+        .emitted(light_rec.u, light_rec.v, &light_rec.p);
+
+    let weight = power_heuristic(light_pdf, material_pdf.value(shadow_ray.direction));
+    f * emitted * weight / light_pdf
+}
+
+// One MIS-weighted sample of the background, which only counts if nothing is in the way.
+fn sample_background(
+    r: &Ray,
+    rec: &HitRecord,
+    srec: &ScatterRecord,
+    material_pdf: &dyn Pdf,
+    scene: &Scene,
+) -> Color {
+    let Some(direction) = scene.background.random() else {
+        return Color::default();
+    };
+    let background_pdf = scene.background.pdf_value(&direction);
+    if background_pdf <= 0.0 {
+        return Color::default();
+    }
+
+    let shadow_ray = Ray::new(rec.p, direction, r.time);
+    let f = rec.mat_ptr.eval(r, rec, srec, &shadow_ray);
+    if f == Color::default() || scene.hit(&shadow_ray, 0.001, f64::INFINITY).is_some() {
+        return Color::default();
+    }
+
+    let weight = power_heuristic(background_pdf, material_pdf.value(direction));
+    f * scene.background.value(&shadow_ray) * weight / background_pdf
+}
+
+// Direct light from every delta light. BSDF sampling can never find these lights, so
+// their contribution is counted in full here.
+fn sample_delta_lights(r: &Ray, rec: &HitRecord, srec: &ScatterRecord, scene: &Scene) -> Color {
+    let mut color = Color::default();
+    for light in &scene.delta_lights {
+        let Some(sample) = light.sample(&rec.p) else {
+            continue;
+        };
+        let shadow_ray = Ray::new(rec.p, sample.direction, r.time);
+        let f = rec.mat_ptr.eval(r, rec, srec, &shadow_ray);
+        if f == Color::default()
+            || scene
+                .hit(&shadow_ray, 0.001, sample.distance - 0.001)
+                .is_some()
+        {
+            continue;
+        }
+        color += f * sample.irradiance;
+    }
+    color
+}
+
+pub fn power_heuristic(pdf_a: f64, pdf_b: f64) -> f64 {
+    let a = pdf_a * pdf_a;
+    let b = pdf_b * pdf_b;
+    if a + b == 0.0 { 0.0 } else { a / (a + b) }
+}
+
+// White where a cosine-distributed ray from the hit point escapes within `distance`.
+pub struct AmbientOcclusion {
+    distance: f64,
+}
+
+impl AmbientOcclusion {
+    pub fn new(distance: f64) -> Self {
+        Self { distance }
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn ray_color(&self, r: Ray, scene: &Scene) -> Color {
+        let Some(rec) = scene.hit(&r, 0.001, f64::INFINITY) else {
+            return Color::new(1.0, 1.0, 1.0);
+        };
+
+        let direction = rec.normal + Vec3::random_unit_vector();
+        let probe = Ray::new(rec.p, direction, r.time);
+        let max_t = self.distance / direction.length();
+        if scene.hit(&probe, 0.001, max_t).is_some() {
+            Color::default()
+        } else {
+            Color::new(1.0, 1.0, 1.0)
+        }
+    }
+}
+
+// Visualizes the shading normal at the first hit, mapped from [-1, 1] to [0, 1].
+pub struct Normals;
+
+impl Integrator for Normals {
+    fn ray_color(&self, r: Ray, scene: &Scene) -> Color {
+        match scene.hit(&r, 0.001, f64::INFINITY) {
+            Some(rec) => 0.5 * (rec.normal + Color::new(1.0, 1.0, 1.0)),
+            None => Color::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarect::{XyRect, XzRect};
+    use crate::background::{Background, SolidBackground};
+    use crate::camera::Camera;
+    use crate::environment::EnvironmentMap;
+    use crate::hittable_list::HittableList;
+    use crate::material::{DiffuseLight, Lambertian, Material};
+    use crate::rtweekend::seed_random;
+    use crate::scenes;
+    use crate::sky::PreethamSky;
+    use crate::texture::SolidColor;
+    use crate::vec3::Point3;
+
+    // Mean and variance of `n` radiance estimates along one camera ray.
+    fn estimate(integrator: &dyn Integrator, scene: &Scene, n: usize) -> (f64, f64) {
+        let samples: Vec<f64> = (0..n)
+            .map(|_| {
+                let r = Ray::new(Point3::new(0.0, 1.0, -2.0), Vec3::new(0.0, -1.0, 1.0), 0.0);
+                let c = integrator.ray_color(r, scene);
+                (c.x + c.y + c.z) / 3.0
+            })
+            .collect();
+        let mean = samples.iter().sum::<f64>() / n as f64;
+        let variance = samples.iter().map(|s| (s - mean) * (s - mean)).sum::<f64>() / n as f64;
+        (mean, variance)
+    }
+
+    #[test]
+    fn test_mis_reduces_variance_at_equal_sample_count() {
+        seed_random(12);
+        let scene = scenes::small_light(1.0);
+        let n = 4000;
+        let (path_mean, path_variance) = estimate(&PathTracer::new(4), &scene, n);
+        let (mis_mean, mis_variance) = estimate(&MisPathTracer::new(4), &scene, n);
+
+        // Both estimators converge to the same radiance...
+        let tolerance = 4.0 * ((path_variance + mis_variance) / n as f64).sqrt();
+        assert!(
+            (path_mean - mis_mean).abs() < tolerance,
+            "means differ: path {} vs mis {}",
+            path_mean,
+            mis_mean
+        );
+        // ...but next-event estimation with MIS gets there with far less noise.
+        assert!(
+            mis_variance < 0.5 * path_variance,
+            "variance: path {} vs mis {}",
+            path_variance,
+            mis_variance
+        );
+    }
+
+    #[test]
+    fn test_spectral_matches_rgb_without_dispersion() {
+        seed_random(18);
+        let scene = scenes::small_light(1.0);
+        let n = 8000;
+        let (mis_mean, mis_variance) = estimate(&MisPathTracer::new(4), &scene, n);
+        let (spectral_mean, spectral_variance) = estimate(&SpectralPathTracer::new(4), &scene, n);
+
+        let tolerance = 4.0 * ((mis_variance + spectral_variance) / n as f64).sqrt();
+        assert!(
+            (mis_mean - spectral_mean).abs() < tolerance + 0.02 * mis_mean,
+            "means differ: mis {} vs spectral {}",
+            mis_mean,
+            spectral_mean
+        );
+    }
+
+    // A background seen as it is but never sampled, as a baseline for those that are.
+    struct Unsampled(Arc<dyn Background>);
+
+    impl Background for Unsampled {
+        fn value(&self, r: &Ray) -> Color {
+            self.0.value(r)
+        }
+    }
+
+    // A gray floor under `background`, which lights it; `estimate` looks down at it.
+    fn floor_under(background: Arc<dyn Background>) -> Scene {
+        let floor = Arc::new(XzRect::new(-100.0, 100.0, -100.0, 100.0, 0.0, gray()));
+        let mut scene = scene_of(HittableList::with_object(floor), Color::default());
+        scene.background = background;
+        scene
+    }
+
+    #[test]
+    fn test_default_integrator_samples_environment_maps() {
+        seed_random(13);
+        // Dim everywhere but for one bright texel above the horizon.
+        let environment = || -> Arc<dyn Background> {
+            let mut texels = vec![Color::new(0.1, 0.1, 0.1); 16 * 8];
+            texels[2 * 16 + 5] = Color::new(100.0, 100.0, 100.0);
+            Arc::new(EnvironmentMap::from_texels(16, 8, texels))
+        };
+        let sampled = floor_under(environment());
+        let unsampled = floor_under(Arc::new(Unsampled(environment())));
+
+        let n = 4000;
+        let (path_mean, path_variance) = estimate(&PathTracer::new(4), &sampled, n);
+        let (mis_mean, mis_variance) = estimate(&MisPathTracer::new(4), &sampled, n);
+        let (_, blind_variance) = estimate(&PathTracer::new(4), &unsampled, n);
+
+        let tolerance = 5.0 * ((path_variance + mis_variance) / n as f64).sqrt();
+        assert!(
+            (path_mean - mis_mean).abs() < tolerance,
+            "means differ: path {} vs mis {}",
+            path_mean,
+            mis_mean
+        );
+        // Finding the bright texel by BSDF sampling alone is far noisier.
+        assert!(
+            path_variance < 0.25 * blind_variance,
+            "variance: sampled {} vs not {}",
+            path_variance,
+            blind_variance
+        );
+    }
+
+    #[test]
+    fn test_default_integrator_samples_the_sun() {
+        seed_random(14);
+        let sky = || -> Arc<dyn Background> { Arc::new(PreethamSky::new(40.0, 30.0, 3.0)) };
+        let sampled = floor_under(sky());
+        let unsampled = floor_under(Arc::new(Unsampled(sky())));
+
+        let n = 4000;
+        let (path_mean, path_variance) = estimate(&PathTracer::new(4), &sampled, n);
+        let (mis_mean, mis_variance) = estimate(&MisPathTracer::new(4), &sampled, n);
+        let (blind_mean, _) = estimate(&PathTracer::new(4), &unsampled, n);
+
+        let tolerance = 5.0 * ((path_variance + mis_variance) / n as f64).sqrt();
+        assert!(
+            (path_mean - mis_mean).abs() < tolerance,
+            "means differ: path {} vs mis {}",
+            path_mean,
+            mis_mean
+        );
+        // The tiny disk is all but invisible to BSDF sampling, yet it outshines the sky.
+        assert!(
+            blind_mean < 0.5 * path_mean,
+            "{} vs {}",
+            blind_mean,
+            path_mean
+        );
+    }
+
+    #[test]
+    fn test_random_walk_conserves_energy() {
+        use crate::hittable::Sphere;
+        use crate::material::Subsurface;
+
+        seed_random(21);
+        // A non-absorbing translucent sphere in a white furnace, with a different mean
+        // free path per channel, lets every bit of light back out.
+        let sphere = Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, 3.0),
+            1.0,
+            Arc::new(Subsurface::new(
+                Color::new(1.0, 1.0, 1.0),
+                Color::new(0.2, 0.25, 0.3),
+                1.4,
+            )),
+        ));
+        let white = Color::new(1.0, 1.0, 1.0);
+        let mut scene = scene_of(HittableList::with_object(sphere), white);
+
+        // Every tracer walks the same way, but the light sampling around the walk differs.
+        // A white environment map is the same furnace, sampled directly as well.
+        let furnaces: [Arc<dyn Background>; 2] = [
+            Arc::new(SolidBackground::new(white)),
+            Arc::new(EnvironmentMap::from_texels(4, 2, vec![white; 8])),
+        ];
+        let n = 20_000;
+        for background in furnaces {
+            scene.background = background;
+            for name in ["path", "mis", "spectral"] {
+                let integrator = by_name(name, 200).unwrap();
+                let mut mean = Color::default();
+                for _ in 0..n {
+                    let r = Ray::new(Point3::new(0.3, 0.2, 0.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+                    mean += integrator.ray_color(r, &scene) / n as f64;
+                }
+                assert!((mean - white).length() < 0.03, "{} {}", name, mean);
+            }
+        }
+    }
+
+    #[test]
+    fn test_emitters_seen_directly_return_their_radiance() {
+        let radiance = Color::new(4.0, 2.0, 1.0);
+        let mut world = HittableList::new();
+        world.add(Arc::new(XyRect::new(
+            -1.0,
+            1.0,
+            -1.0,
+            1.0,
+            2.0,
+            Arc::new(DiffuseLight::new(Arc::new(SolidColor::new(radiance)))),
+        )));
+        let scene = scene_of(world, Color::default());
+        let r = || Ray::new(Point3::default(), Vec3::new(0.1, 0.2, 1.0), 0.0);
+        assert_eq!(PathTracer::new(4).ray_color(r(), &scene), radiance);
+        assert_eq!(MisPathTracer::new(4).ray_color(r(), &scene), radiance);
+    }
+
+    // `world` against `background`, seen by a camera at the origin looking down +z.
+    fn scene_of(world: HittableList, background: Color) -> Scene {
+        let cam = Camera::new(
+            Point3::default(),
+            Point3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            1.0,
+            0.0,
+            1.0,
+        );
+        Scene::new(world, Arc::new(SolidBackground::new(background)), cam)
+    }
+
+    fn gray() -> Arc<dyn Material> {
+        Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+            0.5, 0.5, 0.5,
+        ))))
+    }
+
+    #[test]
+    fn test_by_name() {
+        for name in ["path", "mis", "spectral", "ambient_occlusion", "normals"] {
+            assert!(by_name(name, 4).is_some(), "{}", name);
+        }
+        assert!(by_name("whitted", 4).is_none());
+    }
+
+    #[test]
+    fn test_normals_map_to_colors() {
+        let mut world = HittableList::new();
+        world.add(Arc::new(XyRect::new(-1.0, 1.0, -1.0, 1.0, 2.0, gray())));
+        let scene = scene_of(world, Color::new(1.0, 1.0, 1.0));
+        // The quad faces the camera with the normal (0, 0, -1).
+        let r = Ray::new(Point3::default(), Vec3::new(0.1, 0.0, 1.0), 0.0);
+        assert_eq!(Normals.ray_color(r, &scene), Color::new(0.5, 0.5, 0.0));
+        let r = Ray::new(Point3::default(), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert_eq!(Normals.ray_color(r, &scene), Color::default());
+    }
+
+    #[test]
+    fn test_ambient_occlusion() {
+        seed_random(10);
+        // A floor, and a ceiling over half of it.
+        let mut world = HittableList::new();
+        world.add(Arc::new(XzRect::new(
+            -50.0,
+            50.0,
+            -50.0,
+            50.0,
+            -1.0,
+            gray(),
+        )));
+        world.add(Arc::new(XzRect::new(-50.0, 50.0, 0.0, 50.0, 1.0, gray())));
+        let scene = scene_of(world, Color::default());
+        let ao = AmbientOcclusion::new(f64::INFINITY);
+        let toward = |z: f64| {
+            (0..100)
+                .map(|_| {
+                    ao.ray_color(
+                        Ray::new(Point3::default(), Vec3::new(0.0, -1.0, z), 0.0),
+                        &scene,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        // Far under the ceiling every probe is blocked, and out in the open none are.
+        assert!(toward(10.0).iter().all(|c| *c == Color::default()));
+        assert!(
+            toward(-10.0)
+                .iter()
+                .all(|c| *c == Color::new(1.0, 1.0, 1.0))
+        );
+        // Within a short distance the ceiling doesn't count.
+        let near = AmbientOcclusion::new(0.5);
+        let r = Ray::new(Point3::default(), Vec3::new(0.0, -1.0, 10.0), 0.0);
+        assert_eq!(near.ray_color(r, &scene), Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_power_heuristic() {
+        assert_eq!(power_heuristic(1.0, 0.0), 1.0);
+        assert_eq!(power_heuristic(0.0, 0.0), 0.0);
+        assert!((power_heuristic(1.0, 1.0) - 0.5).abs() < 1e-12);
+        assert!((power_heuristic(2.0, 1.0) + power_heuristic(1.0, 2.0) - 1.0).abs() < 1e-12);
+    }
+}
@@ -1,11 +1,36 @@
 pub mod aabb;
+pub mod aabox;
+pub mod aarect;
+pub mod background;
+pub mod bump;
 pub mod bvh;
 pub mod camera;
 pub mod config;
+pub mod constant_medium;
+pub mod environment;
 pub mod hittable;
 pub mod hittable_list;
+pub mod integrator;
+pub mod light;
+pub mod mask;
+pub mod mat4;
 pub mod material;
+pub mod microfacet;
+pub mod moving_sphere;
+pub mod nested;
+pub mod obj;
+pub mod onb;
+pub mod pdf;
+pub mod perlin;
+pub mod principled;
 pub mod ray;
+pub mod render;
 pub mod rtweekend;
+pub mod scenes;
+pub mod sky;
+pub mod spectrum;
 pub mod texture;
+pub mod thin_film;
+pub mod transform;
+pub mod triangle;
 pub mod vec3;
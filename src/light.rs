@@ -0,0 +1,174 @@
+use crate::rtweekend::degrees_to_radians;
+use crate::vec3::{Color, Point3, Vec3};
+
+// Incident light from a delta light at a shading point.
+pub struct LightSample {
+    pub direction: Vec3,   // unit vector from the shading point toward the light
+    pub distance: f64,     // how far a shadow ray must stay unblocked
+    pub irradiance: Color, // arriving head-on, after the falloff with distance
+}
+
+// A light without geometry. Rays can never hit one, so it only contributes through
+// explicit sampling from the integrator.
+pub trait Light: Send + Sync {
+    fn sample(&self, p: &Point3) -> Option<LightSample>;
+}
+
+// Emits `intensity` equally in every direction from a single position.
+pub struct PointLight {
+    position: Point3,
+    intensity: Color,
+}
+
+impl PointLight {
+    pub fn new(position: Point3, intensity: Color) -> Self {
+        Self {
+            position,
+            intensity,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, p: &Point3) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance_squared = to_light.length_squared();
+        if distance_squared == 0.0 {
+            return None;
+        }
+        let distance = distance_squared.sqrt();
+        Some(LightSample {
+            direction: to_light / distance,
+            distance,
+            irradiance: self.intensity / distance_squared,
+        })
+    }
+}
+
+// A point light restricted to a cone, fading smoothly from full intensity inside
+// `falloff_start` to nothing at `cone_angle` (both half-angles in degrees).
+pub struct SpotLight {
+    position: Point3,
+    direction: Vec3,
+    intensity: Color,
+    cos_cone_angle: f64,
+    cos_falloff_start: f64,
+}
+
+impl SpotLight {
+    pub fn new(
+        position: Point3,
+        direction: Vec3,
+        intensity: Color,
+        cone_angle: f64,
+        falloff_start: f64,
+    ) -> Self {
+        let cone_angle = degrees_to_radians(cone_angle);
+        let falloff_start = degrees_to_radians(falloff_start).min(cone_angle);
+        Self {
+            position,
+            direction: direction.unit_vector(),
+            intensity,
+            cos_cone_angle: cone_angle.cos(),
+            cos_falloff_start: falloff_start.cos(),
+        }
+    }
+
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta >= self.cos_falloff_start {
+            return 1.0;
+        }
+        if cos_theta <= self.cos_cone_angle {
+            return 0.0;
+        }
+        let t = (cos_theta - self.cos_cone_angle) / (self.cos_falloff_start - self.cos_cone_angle);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, p: &Point3) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance_squared = to_light.length_squared();
+        if distance_squared == 0.0 {
+            return None;
+        }
+        let distance = distance_squared.sqrt();
+        let direction = to_light / distance;
+
+        let falloff = self.falloff(-direction.dot(self.direction));
+        if falloff == 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            direction,
+            distance,
+            irradiance: falloff * self.intensity / distance_squared,
+        })
+    }
+}
+
+// Parallel light, like the sun, arriving along `direction` with the given irradiance.
+pub struct DirectionalLight {
+    direction: Vec3,
+    irradiance: Color,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vec3, irradiance: Color) -> Self {
+        Self {
+            direction: direction.unit_vector(),
+            irradiance,
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _p: &Point3) -> Option<LightSample> {
+        Some(LightSample {
+            direction: -self.direction,
+            distance: f64::INFINITY,
+            irradiance: self.irradiance,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_light_falls_off_with_distance_squared() {
+        let light = PointLight::new(Point3::new(0.0, 4.0, 0.0), Color::new(16.0, 16.0, 16.0));
+        let near = light.sample(&Point3::new(0.0, 2.0, 0.0)).unwrap();
+        let far = light.sample(&Point3::new(0.0, 0.0, 0.0)).unwrap();
+        assert_eq!(near.direction, Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(far.distance, 4.0);
+        assert_eq!(near.irradiance, 4.0 * far.irradiance);
+    }
+
+    #[test]
+    fn test_spot_light_cone() {
+        let light = SpotLight::new(
+            Point3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+            30.0,
+            20.0,
+        );
+        // Straight below is fully lit, 25 degrees off axis is fading, 45 degrees is dark.
+        assert_eq!(
+            light
+                .sample(&Point3::new(0.0, 0.0, 0.0))
+                .unwrap()
+                .irradiance
+                .x,
+            1.0
+        );
+        let edge = light
+            .sample(&Point3::new(25.0f64.to_radians().tan(), 0.0, 0.0))
+            .unwrap();
+        assert!(edge.irradiance.x > 0.0 && edge.irradiance.x < 1.0);
+        assert!(light.sample(&Point3::new(1.0, 0.0, 0.0)).is_none());
+    }
+}
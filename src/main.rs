@@ -1,123 +1,12 @@
-use indicatif::{ParallelProgressIterator, ProgressBar, ProgressStyle};
-use myraytracing::camera::Camera;
+use myraytracing::background::SkyGradient;
 use myraytracing::config::Settings;
-use myraytracing::hittable::{Hittable, Sphere};
-use myraytracing::hittable_list::HittableList;
-use myraytracing::material::{Dielectric, Lambertian, Metal};
-use myraytracing::ray::Ray;
-use myraytracing::rtweekend::random_double;
-use myraytracing::texture::{CheckerTexture, SolidColor};
-use myraytracing::vec3::{Color, Point3, Vec3};
-use rayon::prelude::*;
+use myraytracing::environment::EnvironmentMap;
+use myraytracing::integrator;
+use myraytracing::render::render;
+use myraytracing::scenes;
+use myraytracing::sky::PreethamSky;
 use std::sync::Arc;
 
-fn ray_color(mut r: Ray, world: &dyn Hittable, depth: u32) -> Color {
-    let mut attenuation = Color::new(1.0, 1.0, 1.0);
-    let mut current_depth = depth;
-
-    while current_depth > 0 {
-        if let Some(rec) = world.hit(&r, 0.001, f64::INFINITY) {
-            let mut scattered = Ray::new(Point3::default(), Vec3::default());
-            let mut scattered_attenuation = Color::default();
-            if rec
-                .mat_ptr
-                .scatter(&r, &rec, &mut scattered_attenuation, &mut scattered)
-            {
-                attenuation = attenuation * scattered_attenuation;
-                r = scattered;
-            } else {
-                return Color::default();
-            }
-        } else {
-            let unit_direction = r.direction.unit_vector();
-            let t = 0.5 * (unit_direction.y + 1.0);
-            let background_color =
-                (1.0 - t) * Color::new(1.0, 1.0, 1.0) + t * Color::new(0.5, 0.7, 1.0);
-            return attenuation * background_color;
-        }
-        current_depth -= 1;
-    }
-
-    Color::default() // If max_depth is reached, ray_color is black.
-}
-
-fn write_color(color: Color) -> image::Rgb<u8> {
-    let r = (256.0 * color.x.sqrt().clamp(0.0, 0.9999)) as u8;
-    let g = (256.0 * color.y.sqrt().clamp(0.0, 0.9999)) as u8;
-    let b = (256.0 * color.z.sqrt().clamp(0.0, 0.9999)) as u8;
-
-    image::Rgb([r, g, b])
-}
-
-fn random_scene() -> HittableList {
-    let mut world = HittableList::new();
-
-    let checker = Arc::new(Lambertian::new(Arc::new(CheckerTexture::new(
-        Arc::new(SolidColor::new(Color::new(0.2, 0.3, 0.1))),
-        Arc::new(SolidColor::new(Color::new(0.9, 0.9, 0.9))),
-    ))));
-
-    world.add(Arc::new(Sphere::new(
-        Point3::new(0.0, -1000.0, 0.0),
-        1000.0,
-        checker,
-    )));
-
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat = random_double();
-            let center = Point3::new(
-                a as f64 + 0.9 * random_double(),
-                0.2,
-                b as f64 + 0.9 * random_double(),
-            );
-
-            if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
-                let sphere_material: Arc<dyn myraytracing::material::Material>;
-                if choose_mat < 0.8 {
-                    // diffuse
-                    let albedo = Color::random() * Color::random();
-                    sphere_material = Arc::new(Lambertian::new(Arc::new(SolidColor::new(albedo))));
-                    world.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
-                } else if choose_mat < 0.95 {
-                    // metal
-                    let albedo = Color::random_range(0.5, 1.0);
-                    let fuzz = random_double();
-                    sphere_material = Arc::new(Metal::new(albedo, fuzz));
-                    world.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
-                } else {
-                    // glass
-                    sphere_material = Arc::new(Dielectric::new(1.5));
-                    world.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
-                }
-            }
-        }
-    }
-
-    let material1 = Arc::new(Dielectric::new(1.5));
-    world.add(Arc::new(Sphere::new(
-        Point3::new(0.0, 1.0, 0.0),
-        1.0,
-        material1,
-    )));
-    let material2 = Arc::new(Lambertian::new(Arc::new(SolidColor::new(Color::new(
-        0.4, 0.2, 0.1,
-    )))));
-    world.add(Arc::new(Sphere::new(
-        Point3::new(-4.0, 1.0, 0.0),
-        1.0,
-        material2,
-    )));
-    let material3 = Arc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0));
-    world.add(Arc::new(Sphere::new(
-        Point3::new(4.0, 1.0, 0.0),
-        1.0,
-        material3,
-    )));
-
-    world
-}
-
 fn main() {
     let settings = Settings::new();
     let aspect_ratio = settings.aspect_ratio;
@@ -127,55 +16,42 @@ fn main() {
     let samples_per_pixel = settings.samples_per_pixel;
     let max_depth = settings.max_depth;
 
-    let world = random_scene();
-
-    let lookfrom = Point3::new(13.0, 2.0, 3.0);
-    let lookat = Point3::new(0.0, 0.0, 0.0);
-    let vup = Vec3::new(0.0, 1.0, 0.0);
-    let dist_to_focus = 10.0;
-    let aperture = 0.1;
-
-    let cam = Camera::new(
-        lookfrom,
-        lookat,
-        vup,
-        20.0, // vfov
-        aspect_ratio,
-        aperture,
-        dist_to_focus,
-    );
-
-    let mut imgbuf = image::ImageBuffer::new(image_width, image_height);
-
-    let pb = ProgressBar::new((image_width * image_height) as u64);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template(
-                "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})",
+    let mut scene = scenes::by_name(&settings.scene, aspect_ratio)
+        .unwrap_or_else(|| panic!("Unknown scene: {}", settings.scene));
+    match settings.background.as_deref() {
+        None => {}
+        Some("sky") => {
+            let sky = PreethamSky::new(
+                settings.sun_elevation,
+                settings.sun_azimuth,
+                settings.turbidity,
             )
-            .unwrap()
-            .progress_chars("#>-"),
+            .with_intensity(settings.sky_intensity);
+            scene.background = Arc::new(sky);
+        }
+        Some("gradient") => scene.background = Arc::new(SkyGradient::default()),
+        Some(name) => panic!("Unknown background: {}", name),
+    }
+    if let Some(path) = &settings.environment_map {
+        let environment = EnvironmentMap::open(path)
+            .unwrap_or_else(|e| panic!("Failed to load {}: {}", path, e))
+            .with_rotation(settings.environment_rotation)
+            .with_intensity(settings.environment_intensity);
+        scene.background = Arc::new(environment);
+    }
+    if settings.bvh_report {
+        eprintln!("BVH: {}", scene.bvh_report);
+    }
+    let integrator = integrator::by_name(&settings.integrator, max_depth)
+        .unwrap_or_else(|| panic!("Unknown integrator: {}", settings.integrator));
+
+    let imgbuf = render(
+        &scene,
+        integrator.as_ref(),
+        image_width,
+        image_height,
+        samples_per_pixel,
     );
 
-    let pixels: Vec<(u32, u32, &mut image::Rgb<u8>)> = imgbuf.enumerate_pixels_mut().collect();
-
-    pixels
-        .into_par_iter()
-        .progress_with(pb)
-        .for_each(|(i, j, pixel)| {
-            let mut pixel_color = Color::default();
-
-            for _ in 0..samples_per_pixel {
-                let u: f64 = (i as f64 + random_double()) / (image_width - 1) as f64;
-                let v: f64 =
-                    ((image_height - j - 1) as f64 + random_double()) / (image_height - 1) as f64;
-
-                let r = cam.get_ray(u, v);
-                pixel_color += ray_color(r, &world, max_depth);
-            }
-            pixel_color /= samples_per_pixel as f64;
-            *pixel = write_color(pixel_color);
-        });
-
     imgbuf.save(&settings.output_filename).unwrap();
 }
@@ -0,0 +1,140 @@
+use crate::hittable::HitRecord;
+use crate::material::{Material, ScatterRecord};
+use crate::nested::Medium;
+use crate::ray::Ray;
+use crate::texture::Texture;
+use crate::vec3::{Color, Point3};
+use std::sync::Arc;
+
+// Cuts `base` out where the opacity texture, averaged over its channels, is below one.
+// Rays pass through a partly opaque surface at random in proportion, unless a threshold
+// is set, in which case it is either solid or a hole. Load image masks with
+// `ImageTexture::open_alpha`.
+pub struct AlphaMask {
+    pub base: Arc<dyn Material>,
+    pub opacity: Arc<dyn Texture>,
+    pub threshold: Option<f64>,
+}
+
+impl AlphaMask {
+    pub fn new(base: Arc<dyn Material>, opacity: Arc<dyn Texture>) -> Self {
+        Self {
+            base,
+            opacity,
+            threshold: None,
+        }
+    }
+
+    // Keeps the surface where the opacity reaches `threshold` and cuts it away elsewhere,
+    // for hard-edged cut-outs that don't speckle.
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = Some(threshold);
+        self
+    }
+}
+
+impl Material for AlphaMask {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        self.base.scatter(r_in, rec, srec)
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        self.base.scattering_pdf(r_in, rec, scattered)
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, srec: &ScatterRecord, scattered: &Ray) -> Color {
+        self.base.eval(r_in, rec, srec, scattered)
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Point3) -> Color {
+        self.base.emitted(u, v, p)
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        self.base.medium()
+    }
+
+    fn opacity(&self, u: f64, v: f64, p: &Point3) -> f64 {
+        let c = self.opacity.value(u, v, p);
+        let alpha = ((c.x + c.y + c.z) / 3.0).clamp(0.0, 1.0) * self.base.opacity(u, v, p);
+        match self.threshold {
+            Some(threshold) => {
+                if alpha >= threshold {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            None => alpha,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarect::XyRect;
+    use crate::background::SolidBackground;
+    use crate::camera::Camera;
+    use crate::hittable_list::HittableList;
+    use crate::material::Lambertian;
+    use crate::scenes::Scene;
+    use crate::texture::SolidColor;
+    use crate::vec3::Vec3;
+
+    fn gray() -> Arc<dyn Material> {
+        Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+            0.5, 0.5, 0.5,
+        ))))
+    }
+
+    // A quad cut out with `opacity` in front of a solid one.
+    fn scene(opacity: Arc<dyn Material>) -> Scene {
+        let mut world = HittableList::new();
+        world.add(Arc::new(XyRect::new(-1.0, 1.0, -1.0, 1.0, 1.0, opacity)));
+        world.add(Arc::new(XyRect::new(-1.0, 1.0, -1.0, 1.0, 2.0, gray())));
+        let cam = Camera::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            1.0,
+            0.0,
+            1.0,
+        );
+        Scene::new(world, Arc::new(SolidBackground::new(Color::default())), cam)
+    }
+
+    #[test]
+    fn test_threshold_cuts_hard_holes() {
+        let mask = |alpha: f64| {
+            AlphaMask::new(gray(), Arc::new(SolidColor::from_rgb(alpha, alpha, alpha)))
+                .with_threshold(0.5)
+        };
+        let p = Point3::new(0.0, 0.0, 0.0);
+        assert_eq!(mask(0.4).opacity(0.0, 0.0, &p), 0.0);
+        assert_eq!(mask(0.6).opacity(0.0, 0.0, &p), 1.0);
+
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let hit = scene(Arc::new(mask(0.4))).hit(&r, 0.001, f64::INFINITY);
+        assert!((hit.unwrap().t - 2.0).abs() < 1e-9);
+        let hit = scene(Arc::new(mask(0.6))).hit(&r, 0.001, f64::INFINITY);
+        assert!((hit.unwrap().t - 1.0).abs() < 1e-9);
+        // Shadow rays stopping short of the back quad see through the hole.
+        assert!(scene(Arc::new(mask(0.4))).hit(&r, 0.001, 1.5).is_none());
+    }
+
+    #[test]
+    fn test_partial_opacity_stops_that_share_of_rays() {
+        let scene = scene(Arc::new(AlphaMask::new(
+            gray(),
+            Arc::new(SolidColor::from_rgb(0.3, 0.3, 0.3)),
+        )));
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let n = 20000;
+        let stopped = (0..n)
+            .filter(|_| scene.hit(&r, 0.001, f64::INFINITY).unwrap().t < 1.5)
+            .count();
+        assert!((stopped as f64 / n as f64 - 0.3).abs() < 0.02);
+    }
+}
@@ -1,18 +1,67 @@
 use crate::hittable::HitRecord;
+use crate::microfacet::{
+    TrowbridgeReitz, fresnel_conductor, fresnel_dielectric, generalized_half_vector, refract,
+};
+use crate::nested::{Medium, medium_id};
+use crate::onb::Onb;
+use crate::pdf::{CosinePdf, Pdf, SpherePdf};
 use crate::ray::Ray;
-use crate::rtweekend::random_double;
+use crate::rtweekend::{PI, random_double};
+use crate::spectrum::{Dispersion, reflectance_to_rgb, rgb_to_spectrum};
 use crate::texture::Texture;
-use crate::vec3::{Color, Vec3};
+use crate::thin_film::ThinFilm;
+use crate::vec3::{Color, Point3, Vec3};
 use std::sync::Arc;
 
+// The outcome of a scatter: either a single specular ray or a density to sample from.
+pub struct ScatterRecord {
+    pub specular_ray: Ray,
+    pub is_specular: bool,
+    pub attenuation: Color,
+    pub pdf_ptr: Option<Arc<dyn Pdf>>,
+    pub wavelength_dependent: bool, // the scatter depended on r_in.wavelength
+}
+
+impl Default for ScatterRecord {
+    fn default() -> Self {
+        Self {
+            specular_ray: Ray::new(Point3::default(), Vec3::default(), 0.0),
+            is_specular: false,
+            attenuation: Color::default(),
+            pdf_ptr: None,
+            wavelength_dependent: false,
+        }
+    }
+}
+
 pub trait Material: Send + Sync {
-    fn scatter(
-        &self,
-        r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Color,
-        scattered: &mut Ray,
-    ) -> bool;
+    fn scatter(&self, _r_in: &Ray, _rec: &HitRecord, _srec: &mut ScatterRecord) -> bool {
+        false
+    }
+
+    // Density of scattering toward `scattered`; the BRDF times cosine is attenuation * this.
+    fn scattering_pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        0.0
+    }
+
+    // The BRDF times cosine toward `scattered`, for a non-specular `srec` from `scatter`.
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, srec: &ScatterRecord, scattered: &Ray) -> Color {
+        srec.attenuation * self.scattering_pdf(r_in, rec, scattered)
+    }
+
+    fn emitted(&self, _u: f64, _v: f64, _p: &Point3) -> Color {
+        Color::default()
+    }
+
+    // The interior a transmitted path enters, for dielectrics that take part in nesting.
+    fn medium(&self) -> Option<Medium> {
+        None
+    }
+
+    // The chance that a ray stops at the surface rather than passing straight through.
+    fn opacity(&self, _u: f64, _v: f64, _p: &Point3) -> f64 {
+        1.0
+    }
 }
 
 pub struct Lambertian {
@@ -26,23 +75,173 @@ impl Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(
-        &self,
-        _r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Color,
-        scattered: &mut Ray,
-    ) -> bool {
-        let scatter_direction = rec.normal + Vec3::random_unit_vector();
-        *scattered = Ray::new(rec.p, scatter_direction);
-        *attenuation = self.albedo.value(rec.u, rec.v, &rec.p);
+    fn scatter(&self, _r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        srec.is_specular = false;
+        srec.attenuation = self.albedo.value(rec.u, rec.v, &rec.p);
+        srec.pdf_ptr = Some(Arc::new(CosinePdf::new(rec.normal)));
+        true
+    }
+
+    fn scattering_pdf(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cosine = rec.normal.dot(scattered.direction.unit_vector());
+        if cosine < 0.0 { 0.0 } else { cosine / PI }
+    }
+}
+
+// Rough diffuse reflection from a surface of V-shaped grooves, after Oren and Nayar.
+// Rougher surfaces look flatter and brighter toward the light than a Lambertian one.
+pub struct OrenNayar {
+    pub albedo: Arc<dyn Texture>,
+    a: f64,
+    b: f64,
+}
+
+impl OrenNayar {
+    // `sigma` is the standard deviation of the groove slopes, in degrees; 0 is Lambertian.
+    pub fn new(albedo: Arc<dyn Texture>, sigma: f64) -> Self {
+        let sigma2 = sigma.to_radians().powi(2);
+        Self {
+            albedo,
+            a: 1.0 - sigma2 / (2.0 * (sigma2 + 0.33)),
+            b: 0.45 * sigma2 / (sigma2 + 0.09),
+        }
+    }
+}
+
+impl Material for OrenNayar {
+    fn scatter(&self, _r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        srec.is_specular = false;
+        srec.attenuation = self.albedo.value(rec.u, rec.v, &rec.p);
+        srec.pdf_ptr = Some(Arc::new(CosinePdf::new(rec.normal)));
+        true
+    }
+
+    fn scattering_pdf(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cosine = rec.normal.dot(scattered.direction.unit_vector());
+        if cosine < 0.0 { 0.0 } else { cosine / PI }
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, srec: &ScatterRecord, scattered: &Ray) -> Color {
+        let wo = -r_in.direction.unit_vector();
+        let wi = scattered.direction.unit_vector();
+        let n = rec.normal;
+        let cos_i = n.dot(wi);
+        let cos_o = n.dot(wo).max(1e-6);
+        if cos_i <= 0.0 {
+            return Color::default();
+        }
+        let sin_i = (1.0 - cos_i * cos_i).max(0.0).sqrt();
+        let sin_o = (1.0 - cos_o * cos_o).max(0.0).sqrt();
+
+        // Only the part of the light arriving from the viewer's side is retro-reflected.
+        let max_cos = if sin_i > 1e-4 && sin_o > 1e-4 {
+            ((wi - cos_i * n).dot(wo - cos_o * n) / (sin_i * sin_o)).max(0.0)
+        } else {
+            0.0
+        };
+        let (sin_alpha, tan_beta) = if cos_i > cos_o {
+            (sin_o, sin_i / cos_i)
+        } else {
+            (sin_i, sin_o / cos_o)
+        };
+        srec.attenuation * (self.a + self.b * max_cos * sin_alpha * tan_beta) * cos_i / PI
+    }
+}
+
+// Fabric with a diffuse base under a sheen of fibers that catch the light at grazing
+// angles, using the "Charlie" sheen distribution of Estevez and Kulla. The base only
+// gets the light the sheen doesn't reflect.
+pub struct Velvet {
+    pub albedo: Arc<dyn Texture>,
+    pub sheen: Arc<dyn Texture>,
+    pub roughness: f64,
+    sheen_albedo: [f64; VELVET_TABLE_SIZE], // of a white sheen, by cosine of the view
+}
+
+const VELVET_TABLE_SIZE: usize = 32;
+
+impl Velvet {
+    pub fn new(albedo: Arc<dyn Texture>, sheen: Arc<dyn Texture>, roughness: f64) -> Self {
+        let roughness = roughness.clamp(0.05, 1.0);
+        // Integrate the sheen lobe over the hemisphere by the midpoint rule.
+        let steps = 64;
+        let mut sheen_albedo = [0.0; VELVET_TABLE_SIZE];
+        for (i, e) in sheen_albedo.iter_mut().enumerate() {
+            let cos_o = (i as f64 + 0.5) / VELVET_TABLE_SIZE as f64;
+            let wo = Vec3::new((1.0 - cos_o * cos_o).sqrt(), 0.0, cos_o);
+            for j in 0..steps {
+                let cos_i = (j as f64 + 0.5) / steps as f64;
+                let sin_i = (1.0 - cos_i * cos_i).sqrt();
+                for k in 0..steps {
+                    let phi = 2.0 * PI * (k as f64 + 0.5) / steps as f64;
+                    let wi = Vec3::new(sin_i * phi.cos(), sin_i * phi.sin(), cos_i);
+                    let h = (wi + wo).unit_vector();
+                    *e += charlie_sheen(h.z, cos_i, cos_o, roughness) * cos_i / steps as f64
+                        * (2.0 * PI / steps as f64);
+                }
+            }
+        }
+        Self {
+            albedo,
+            sheen,
+            roughness,
+            sheen_albedo,
+        }
+    }
+
+    fn sheen_albedo(&self, cos_o: f64) -> f64 {
+        let x = (cos_o * VELVET_TABLE_SIZE as f64 - 0.5).clamp(0.0, (VELVET_TABLE_SIZE - 1) as f64);
+        let i = (x as usize).min(VELVET_TABLE_SIZE - 2);
+        let t = x - i as f64;
+        (1.0 - t) * self.sheen_albedo[i] + t * self.sheen_albedo[i + 1]
+    }
+}
+
+// The Charlie sheen BRDF, with Neubelt and Pettineo's visibility term, for half vector,
+// light and view cosines.
+fn charlie_sheen(cos_h: f64, cos_i: f64, cos_o: f64, roughness: f64) -> f64 {
+    let inv = 1.0 / roughness;
+    let sin_h = (1.0 - cos_h * cos_h).max(0.0).sqrt();
+    let d = (2.0 + inv) * sin_h.powf(inv) / (2.0 * PI);
+    let v = 1.0 / (4.0 * (cos_i + cos_o - cos_i * cos_o));
+    d * v
+}
+
+impl Material for Velvet {
+    fn scatter(&self, _r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        srec.is_specular = false;
+        srec.attenuation = self.albedo.value(rec.u, rec.v, &rec.p);
+        srec.pdf_ptr = Some(Arc::new(CosinePdf::new(rec.normal)));
         true
     }
+
+    fn scattering_pdf(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cosine = rec.normal.dot(scattered.direction.unit_vector());
+        if cosine < 0.0 { 0.0 } else { cosine / PI }
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, srec: &ScatterRecord, scattered: &Ray) -> Color {
+        let wo = -r_in.direction.unit_vector();
+        let wi = scattered.direction.unit_vector();
+        let n = rec.normal;
+        let cos_i = n.dot(wi);
+        let cos_o = n.dot(wo).clamp(1e-6, 1.0);
+        if cos_i <= 0.0 {
+            return Color::default();
+        }
+
+        let sheen = self.sheen.value(rec.u, rec.v, &rec.p);
+        let h = (wi + wo).unit_vector();
+        let f_sheen = charlie_sheen(n.dot(h), cos_i, cos_o, self.roughness);
+        let dim = 1.0 - sheen.x.max(sheen.y).max(sheen.z).min(1.0) * self.sheen_albedo(cos_o);
+        (srec.attenuation * dim / PI + sheen * f_sheen) * cos_i
+    }
 }
 
 pub struct Metal {
     pub albedo: Color,
     pub fuzz: f64,
+    pub thin_film: Option<ThinFilm>,
 }
 
 impl Metal {
@@ -50,68 +249,864 @@ impl Metal {
         Self {
             albedo,
             fuzz: if fuzz < 1.0 { fuzz } else { 1.0 },
+            thin_film: None,
         }
     }
+
+    // Coats the metal with a film whose interference colors its reflections, as on
+    // heat-tinted steel.
+    pub fn with_thin_film(mut self, thin_film: ThinFilm) -> Self {
+        self.thin_film = Some(thin_film);
+        self
+    }
 }
 
 impl Material for Metal {
-    fn scatter(
-        &self,
-        r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Color,
-        scattered: &mut Ray,
-    ) -> bool {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
         let reflected = Vec3::reflect(&r_in.direction.unit_vector(), &rec.normal);
-        *scattered = Ray::new(rec.p, reflected + self.fuzz * Vec3::random_in_unit_sphere());
-        *attenuation = self.albedo;
-        Vec3::dot(&scattered.direction, rec.normal) > 0.0
+        srec.specular_ray = Ray::new(
+            rec.p,
+            reflected + self.fuzz * Vec3::random_in_unit_sphere(),
+            r_in.time,
+        );
+        srec.attenuation = match self.thin_film {
+            Some(film) => {
+                let cos_theta = rec.normal.dot(-r_in.direction.unit_vector());
+                let n1 = r_in.media.ior();
+                thin_film_reflectance(r_in, srec, |lambda| {
+                    let substrate = rgb_to_spectrum(self.albedo, lambda);
+                    film.reflectance_over_metal(cos_theta, n1, substrate, lambda)
+                })
+            }
+            None => self.albedo,
+        };
+        srec.is_specular = true;
+        srec.pdf_ptr = None;
+        Vec3::dot(&srec.specular_ray.direction, rec.normal) > 0.0
     }
 }
 
 pub struct Dielectric {
     pub ref_idx: f64,
+    pub dispersion: Option<Dispersion>,
+    pub absorption: Color,
+    pub priority: u32,
+    pub thin_film: Option<ThinFilm>,
 }
 
 impl Dielectric {
     pub fn new(ref_idx: f64) -> Self {
-        Self { ref_idx }
+        Self {
+            ref_idx,
+            dispersion: None,
+            absorption: Color::default(),
+            priority: 0,
+            thin_film: None,
+        }
+    }
+
+    // Tints light by exp(-absorption * distance) along the path inside.
+    pub fn with_absorption(mut self, absorption: Color) -> Self {
+        self.absorption = absorption;
+        self
+    }
+
+    // Decides which dielectric owns the space where two of them overlap, so that e.g.
+    // water filling a glass can overlap the glass walls.
+    pub fn with_priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    // Makes the index of refraction depend on the wavelength in spectral rendering;
+    // ref_idx still applies to RGB rendering.
+    pub fn with_dispersion(mut self, dispersion: Dispersion) -> Self {
+        self.dispersion = Some(dispersion);
+        self
+    }
+
+    // Coats the surface with a film whose interference colors its reflections. Over an
+    // index of 1 this is a soap bubble.
+    pub fn with_thin_film(mut self, thin_film: ThinFilm) -> Self {
+        self.thin_film = Some(thin_film);
+        self
     }
 }
 
 impl Material for Dielectric {
-    fn scatter(
-        &self,
-        r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Color,
-        scattered: &mut Ray,
-    ) -> bool {
-        *attenuation = Color::new(1.0, 1.0, 1.0);
-        let etai_over_etat = if rec.front_face {
-            1.0 / self.ref_idx
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        srec.is_specular = true;
+        srec.pdf_ptr = None;
+        srec.attenuation = Color::new(1.0, 1.0, 1.0);
+        let ref_idx = match (self.dispersion, r_in.wavelength) {
+            (Some(dispersion), Some(lambda)) => {
+                srec.wavelength_dependent = true;
+                dispersion.ior(lambda)
+            }
+            _ => self.ref_idx,
+        };
+        // The medium on the other side is whichever one the path is in, or will be in
+        // once it leaves this one.
+        let (n_i, n_t) = if rec.front_face {
+            (r_in.media.ior(), ref_idx)
         } else {
-            self.ref_idx
+            (ref_idx, r_in.media.ior_without(medium_id(self)))
         };
+        let etai_over_etat = n_i / n_t;
         let unit_direction = r_in.direction.unit_vector();
         let cos_theta = rec.normal.dot(-unit_direction).min(1.0);
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
 
         if etai_over_etat * sin_theta > 1.0 {
             let reflected = Vec3::reflect(&unit_direction, &rec.normal);
-            *scattered = Ray::new(rec.p, reflected);
+            srec.specular_ray = Ray::new(rec.p, reflected, r_in.time);
             return true;
         }
 
-        let reflect_prob = Vec3::schlick(cos_theta, etai_over_etat);
+        // A film's colored reflectance is followed with the probability of its average,
+        // and the attenuation makes up the difference per channel.
+        let white = Color::new(1.0, 1.0, 1.0);
+        let (reflect_prob, reflected_attenuation, refracted_attenuation) = match self.thin_film {
+            Some(film) => {
+                let r = thin_film_reflectance(r_in, srec, |lambda| {
+                    film.reflectance(cos_theta, n_i, n_t, lambda)
+                });
+                let p = (r.x + r.y + r.z) / 3.0;
+                (p, r / p, (white - r) / (1.0 - p))
+            }
+            None => (Vec3::schlick(cos_theta, etai_over_etat), white, white),
+        };
         if random_double() < reflect_prob {
             let reflected = Vec3::reflect(&unit_direction, &rec.normal);
-            *scattered = Ray::new(rec.p, reflected);
+            srec.specular_ray = Ray::new(rec.p, reflected, r_in.time);
+            srec.attenuation = reflected_attenuation;
             return true;
         }
 
         let refracted = Vec3::refract(&unit_direction, &rec.normal, etai_over_etat);
-        *scattered = Ray::new(rec.p, refracted);
+        srec.specular_ray = Ray::new(rec.p, refracted, r_in.time);
+        srec.attenuation = refracted_attenuation;
+        true
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        Some(Medium {
+            id: medium_id(self),
+            ior: self.ref_idx,
+            absorption: self.absorption,
+            scattering: Color::default(),
+            priority: self.priority,
+        })
+    }
+}
+
+// A translucent solid such as jade or candle wax: a smooth dielectric surface over an
+// interior that scatters light, which the integrators follow by a random walk until it
+// finds its way out again.
+pub struct Subsurface {
+    pub albedo: Color,
+    pub mean_free_path: Color,
+    pub ior: f64,
+}
+
+impl Subsurface {
+    // `albedo` is the color the object takes on once light has scattered around inside
+    // it, and `mean_free_path` how far light travels between scattering events.
+    pub fn new(albedo: Color, mean_free_path: Color, ior: f64) -> Self {
+        Self {
+            albedo,
+            mean_free_path,
+            ior,
+        }
+    }
+
+    // The albedo of a single scattering event that gives the requested multiple
+    // scattering albedo, after Chiang et al., "Practical and Controllable Subsurface
+    // Scattering for Production Path Tracing".
+    fn single_scattering_albedo(a: f64) -> f64 {
+        let a = a.clamp(0.0, 1.0);
+        let s = 4.09712 + 4.20863 * a - (9.59217 + 41.6808 * a + 17.7126 * a * a).sqrt();
+        (1.0 - s * s).clamp(0.0, 1.0)
+    }
+}
+
+impl Material for Subsurface {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        srec.attenuation = Color::new(1.0, 1.0, 1.0);
+        // Light leaves diffusely, as through a slightly rough surface, which lets the
+        // integrators sample the lights where the walk comes out.
+        if !rec.front_face {
+            srec.is_specular = false;
+            srec.pdf_ptr = Some(Arc::new(CosinePdf::new(-rec.normal)));
+            return true;
+        }
+
+        srec.is_specular = true;
+        srec.pdf_ptr = None;
+        let n_i = r_in.media.ior();
+        let unit_direction = r_in.direction.unit_vector();
+        let cos_theta = rec.normal.dot(-unit_direction);
+        let direction = if random_double() < fresnel_dielectric(cos_theta, self.ior / n_i) {
+            Vec3::reflect(&unit_direction, &rec.normal)
+        } else {
+            Vec3::refract(&unit_direction, &rec.normal, n_i / self.ior)
+        };
+        srec.specular_ray = Ray::new(rec.p, direction, r_in.time);
+        true
+    }
+
+    fn scattering_pdf(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cosine = -rec.normal.dot(scattered.direction.unit_vector());
+        if cosine < 0.0 { 0.0 } else { cosine / PI }
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        let per_channel = |f: &dyn Fn(f64, f64) -> f64| {
+            Color::new(
+                f(self.albedo.x, self.mean_free_path.x),
+                f(self.albedo.y, self.mean_free_path.y),
+                f(self.albedo.z, self.mean_free_path.z),
+            )
+        };
+        Some(Medium {
+            id: medium_id(self),
+            ior: self.ior,
+            absorption: per_channel(&|a, mfp| (1.0 - Self::single_scattering_albedo(a)) / mfp),
+            scattering: per_channel(&|a, mfp| Self::single_scattering_albedo(a) / mfp),
+            priority: 0,
+        })
+    }
+}
+
+// The reflectance of a thin film as a color. Spectral rendering only needs it at the hero
+// wavelength, after which the path carries that wavelength alone.
+fn thin_film_reflectance(
+    r_in: &Ray,
+    srec: &mut ScatterRecord,
+    reflectance: impl Fn(f64) -> f64,
+) -> Color {
+    match r_in.wavelength {
+        Some(lambda) => {
+            srec.wavelength_dependent = true;
+            let r = reflectance(lambda);
+            Color::new(r, r, r)
+        }
+        None => {
+            // Saturated interference colors can fall outside the RGB gamut.
+            let r = reflectance_to_rgb(reflectance);
+            Color::new(
+                r.x.clamp(0.0, 1.0),
+                r.y.clamp(0.0, 1.0),
+                r.z.clamp(0.0, 1.0),
+            )
+        }
+    }
+}
+
+// A smooth clear dielectric coat over any base material, as on car paint. Light the coat
+// doesn't reflect reaches the base, and what the base scatters is dimmed by the coat
+// again on the way out; light reflected back down by the coat is not followed.
+pub struct Coated {
+    pub base: Arc<dyn Material>,
+    pub ior: f64,
+}
+
+impl Coated {
+    pub fn new(base: Arc<dyn Material>, ior: f64) -> Self {
+        Self { base, ior }
+    }
+
+    fn transmittance(&self, rec: &HitRecord, direction: Vec3) -> f64 {
+        1.0 - fresnel_dielectric(rec.normal.dot(direction.unit_vector()), self.ior)
+    }
+}
+
+impl Material for Coated {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        if !rec.front_face {
+            return self.base.scatter(r_in, rec, srec);
+        }
+
+        let unit_direction = r_in.direction.unit_vector();
+        if random_double() < 1.0 - self.transmittance(rec, -unit_direction) {
+            let reflected = Vec3::reflect(&unit_direction, &rec.normal);
+            srec.specular_ray = Ray::new(rec.p, reflected, r_in.time);
+            srec.attenuation = Color::new(1.0, 1.0, 1.0);
+            srec.is_specular = true;
+            srec.pdf_ptr = None;
+            return true;
+        }
+
+        if !self.base.scatter(r_in, rec, srec) {
+            return false;
+        }
+        if srec.is_specular {
+            let t = self.transmittance(rec, srec.specular_ray.direction);
+            srec.attenuation *= t;
+        }
         true
     }
+
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        self.base.scattering_pdf(r_in, rec, scattered)
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, srec: &ScatterRecord, scattered: &Ray) -> Color {
+        let f = self.base.eval(r_in, rec, srec, scattered);
+        if rec.front_face {
+            f * self.transmittance(rec, scattered.direction)
+        } else {
+            f
+        }
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Point3) -> Color {
+        self.base.emitted(u, v, p)
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        self.base.medium()
+    }
+
+    fn opacity(&self, u: f64, v: f64, p: &Point3) -> f64 {
+        self.base.opacity(u, v, p)
+    }
+}
+
+// The frame of the surface at the hit, with u along the tangent so that anisotropic
+// roughness follows the surface's u and v directions.
+pub(crate) fn surface_frame(rec: &HitRecord) -> Onb {
+    Onb::build_from_w_and_u(rec.normal, rec.tangent)
+}
+
+// A rough metal described by its complex index of refraction `eta + i k`.
+pub struct Conductor {
+    pub eta: Color,
+    pub k: Color,
+    pub distribution: TrowbridgeReitz,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness: f64) -> Self {
+        Self {
+            eta,
+            k,
+            distribution: TrowbridgeReitz::from_roughness(roughness, roughness),
+        }
+    }
+
+    // Different roughness along the two tangent directions, as on brushed metal.
+    pub fn with_anisotropic_roughness(mut self, roughness_u: f64, roughness_v: f64) -> Self {
+        self.distribution = TrowbridgeReitz::from_roughness(roughness_u, roughness_v);
+        self
+    }
+
+    pub fn gold(roughness: f64) -> Self {
+        Self::new(
+            Color::new(0.143, 0.374, 1.442),
+            Color::new(3.983, 2.385, 1.603),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f64) -> Self {
+        Self::new(
+            Color::new(0.200, 0.924, 1.102),
+            Color::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    pub fn aluminium(roughness: f64) -> Self {
+        Self::new(
+            Color::new(1.657, 0.880, 0.521),
+            Color::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f64) -> Self {
+        Self::new(
+            Color::new(0.155, 0.117, 0.138),
+            Color::new(4.828, 3.122, 2.147),
+            roughness,
+        )
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        let unit_direction = r_in.direction.unit_vector();
+        if self.distribution.effectively_smooth() {
+            let reflected = Vec3::reflect(&unit_direction, &rec.normal);
+            srec.specular_ray = Ray::new(rec.p, reflected, r_in.time);
+            srec.attenuation = fresnel_conductor(rec.normal.dot(-unit_direction), self.eta, self.k);
+            srec.is_specular = true;
+            srec.pdf_ptr = None;
+            return true;
+        }
+
+        let uvw = surface_frame(rec);
+        srec.is_specular = false;
+        srec.attenuation = Color::new(1.0, 1.0, 1.0);
+        srec.pdf_ptr = Some(Arc::new(ConductorPdf {
+            wo: uvw.to_local(-unit_direction),
+            uvw,
+            distribution: self.distribution,
+        }));
+        true
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, _srec: &ScatterRecord, scattered: &Ray) -> Color {
+        let uvw = surface_frame(rec);
+        let wo = uvw.to_local(-r_in.direction.unit_vector());
+        let wi = uvw.to_local(scattered.direction.unit_vector());
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::default();
+        }
+        let wm = wo + wi;
+        if wm.length_squared() == 0.0 {
+            return Color::default();
+        }
+        let wm = wm.unit_vector();
+
+        // The cosine toward wi cancels against the BRDF's denominator.
+        fresnel_conductor(wo.dot(wm), self.eta, self.k)
+            * self.distribution.d(wm)
+            * self.distribution.g(wo, wi)
+            / (4.0 * wo.z)
+    }
+}
+
+// Mirror reflections about visible microfacet normals.
+struct ConductorPdf {
+    uvw: Onb,
+    wo: Vec3,
+    distribution: TrowbridgeReitz,
+}
+
+impl Pdf for ConductorPdf {
+    fn value(&self, direction: Vec3) -> f64 {
+        let wi = self.uvw.to_local(direction.unit_vector());
+        if wi.z <= 0.0 {
+            return 0.0;
+        }
+        let wm = self.wo + wi;
+        if wm.length_squared() == 0.0 {
+            return 0.0;
+        }
+        let wm = wm.unit_vector();
+        self.distribution.d_visible(self.wo, wm) / (4.0 * self.wo.dot(wm).abs())
+    }
+
+    fn generate(&self) -> Vec3 {
+        let wm = self
+            .distribution
+            .sample_wm(self.wo, random_double(), random_double());
+        self.uvw.local_vec(Vec3::reflect(&-self.wo, &wm))
+    }
+}
+
+// Frosted glass: a dielectric interface that both reflects and transmits through
+// microfacets.
+pub struct RoughDielectric {
+    pub ref_idx: f64,
+    pub distribution: TrowbridgeReitz,
+}
+
+impl RoughDielectric {
+    pub fn new(ref_idx: f64, roughness: f64) -> Self {
+        Self {
+            ref_idx,
+            distribution: TrowbridgeReitz::from_roughness(roughness, roughness),
+        }
+    }
+
+    pub fn with_anisotropic_roughness(mut self, roughness_u: f64, roughness_v: f64) -> Self {
+        self.distribution = TrowbridgeReitz::from_roughness(roughness_u, roughness_v);
+        self
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        // Relative index across the surface, seen from the side the ray arrives on.
+        let eta = if rec.front_face {
+            self.ref_idx
+        } else {
+            1.0 / self.ref_idx
+        };
+        let unit_direction = r_in.direction.unit_vector();
+
+        if self.distribution.effectively_smooth() {
+            let wo = -unit_direction;
+            let reflect_prob = fresnel_dielectric(rec.normal.dot(wo), eta);
+            srec.is_specular = true;
+            srec.pdf_ptr = None;
+            srec.attenuation = Color::new(1.0, 1.0, 1.0);
+            let direction = match refract(wo, rec.normal, eta) {
+                Some(refracted) if random_double() >= reflect_prob => refracted,
+                _ => Vec3::reflect(&unit_direction, &rec.normal),
+            };
+            srec.specular_ray = Ray::new(rec.p, direction, r_in.time);
+            return true;
+        }
+
+        let uvw = surface_frame(rec);
+        srec.is_specular = false;
+        srec.attenuation = Color::new(1.0, 1.0, 1.0);
+        srec.pdf_ptr = Some(Arc::new(RoughDielectricPdf {
+            wo: uvw.to_local(-unit_direction),
+            uvw,
+            eta,
+            distribution: self.distribution,
+        }));
+        true
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, _srec: &ScatterRecord, scattered: &Ray) -> Color {
+        let eta = if rec.front_face {
+            self.ref_idx
+        } else {
+            1.0 / self.ref_idx
+        };
+        let uvw = surface_frame(rec);
+        let wo = uvw.to_local(-r_in.direction.unit_vector());
+        let wi = uvw.to_local(scattered.direction.unit_vector());
+        let Some(wm) = generalized_half_vector(wo, wi, eta) else {
+            return Color::default();
+        };
+
+        let d = self.distribution.d(wm);
+        let g = self.distribution.g(wo, wi);
+        let f = fresnel_dielectric(wo.dot(wm), eta);
+        let value = if wi.z > 0.0 {
+            d * g * f / (4.0 * wo.z * wi.z)
+        } else {
+            let denom = wi.dot(wm) + wo.dot(wm) / eta;
+            d * (1.0 - f) * g * (wi.dot(wm) * wo.dot(wm) / (wi.z * wo.z * denom * denom)).abs()
+        };
+        Color::new(1.0, 1.0, 1.0) * value * wi.z.abs()
+    }
+}
+
+// Reflection or refraction about visible microfacet normals, chosen by Fresnel.
+struct RoughDielectricPdf {
+    uvw: Onb,
+    wo: Vec3,
+    eta: f64,
+    distribution: TrowbridgeReitz,
+}
+
+impl Pdf for RoughDielectricPdf {
+    fn value(&self, direction: Vec3) -> f64 {
+        let wo = self.wo;
+        let wi = self.uvw.to_local(direction.unit_vector());
+        let Some(wm) = generalized_half_vector(wo, wi, self.eta) else {
+            return 0.0;
+        };
+
+        let f = fresnel_dielectric(wo.dot(wm), self.eta);
+        let d_visible = self.distribution.d_visible(wo, wm);
+        if wi.z > 0.0 {
+            d_visible / (4.0 * wo.dot(wm).abs()) * f
+        } else {
+            let denom = wi.dot(wm) + wo.dot(wm) / self.eta;
+            d_visible * wi.dot(wm).abs() / (denom * denom) * (1.0 - f)
+        }
+    }
+
+    fn generate(&self) -> Vec3 {
+        let wm = self
+            .distribution
+            .sample_wm(self.wo, random_double(), random_double());
+        let reflect_prob = fresnel_dielectric(self.wo.dot(wm), self.eta);
+        let wi = match refract(self.wo, wm, self.eta) {
+            Some(refracted) if random_double() >= reflect_prob => refracted,
+            _ => Vec3::reflect(&-self.wo, &wm),
+        };
+        self.uvw.local_vec(wi)
+    }
+}
+
+pub struct DiffuseLight {
+    pub emit: Arc<dyn Texture>,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Arc<dyn Texture>) -> Self {
+        Self { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn emitted(&self, u: f64, v: f64, p: &Point3) -> Color {
+        self.emit.value(u, v, p)
+    }
+}
+
+pub struct Isotropic {
+    pub albedo: Arc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Arc<dyn Texture>) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, _r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        srec.is_specular = false;
+        srec.attenuation = self.albedo.value(rec.u, rec.v, &rec.p);
+        srec.pdf_ptr = Some(Arc::new(SpherePdf));
+        true
+    }
+
+    fn scattering_pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::aarect::XzRect;
+    use crate::hittable::Hittable;
+    use crate::rtweekend::seed_random;
+    use crate::texture::SolidColor;
+    use crate::transform::RotateY;
+
+    fn hit_from_above(mat_ptr: Arc<dyn Material>, front_face: bool) -> (Ray, HitRecord) {
+        let r_in = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.6, -1.0, 0.2), 0.0);
+        let rec = HitRecord {
+            p: Point3::new(0.6, -1.0, 0.2),
+            normal: Vec3::new(0.0, 1.0, 0.0),
+            tangent: Vec3::new(1.0, 0.0, 0.0),
+            bitangent: Vec3::new(0.0, 0.0, 1.0),
+            t: 1.0,
+            u: 0.0,
+            v: 0.0,
+            mat_ptr,
+            front_face,
+        };
+        (r_in, rec)
+    }
+
+    // Estimates the directional albedo both by uniform sampling of the sphere and by the
+    // material's own density, which only agree if eval and the pdf are consistent. Each
+    // sample scatters afresh, and specular samples count their attenuation either way.
+    // Returns the importance sampled estimate once the two agree to within their standard
+    // errors.
+    pub(crate) fn albedo_two_ways(mat_ptr: Arc<dyn Material>, front_face: bool) -> f64 {
+        seed_random(16);
+        let (r_in, rec) = hit_from_above(mat_ptr.clone(), front_face);
+        let mean = |c: Color| (c.x + c.y + c.z) / 3.0;
+
+        let n = 400_000;
+        let estimate = |direction: &dyn Fn(&dyn Pdf) -> (Vec3, f64)| {
+            let mut srec = ScatterRecord::default();
+            assert!(mat_ptr.scatter(&r_in, &rec, &mut srec));
+            if srec.is_specular {
+                return mean(srec.attenuation);
+            }
+            let (d, p) = direction(srec.pdf_ptr.as_deref().unwrap());
+            if p > 0.0 {
+                mean(mat_ptr.eval(&r_in, &rec, &srec, &Ray::new(rec.p, d, 0.0))) / p
+            } else {
+                0.0
+            }
+        };
+        let mean_and_variance = |samples: Vec<f64>| {
+            let mean = samples.iter().sum::<f64>() / n as f64;
+            let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n as f64;
+            (mean, variance)
+        };
+        let (uniform, var_uniform) = mean_and_variance(
+            (0..n)
+                .map(|_| estimate(&|_| (Vec3::random_unit_vector(), 1.0 / (4.0 * PI))))
+                .collect(),
+        );
+        let (sampled, var_sampled) = mean_and_variance(
+            (0..n)
+                .map(|_| {
+                    estimate(&|pdf| {
+                        let d = pdf.generate();
+                        (d, pdf.value(d))
+                    })
+                })
+                .collect(),
+        );
+        let error = ((var_uniform + var_sampled) / n as f64).sqrt();
+        assert!(
+            (uniform - sampled).abs() < 5.0 * error,
+            "{} vs {} (standard error {})",
+            uniform,
+            sampled,
+            error
+        );
+        sampled
+    }
+
+    #[test]
+    fn test_conductor_sampling_matches_eval() {
+        let albedo = albedo_two_ways(Arc::new(Conductor::silver(0.6)), true);
+        assert!(albedo < 1.0, "albedo {}", albedo);
+        let albedo = albedo_two_ways(
+            Arc::new(Conductor::silver(0.1).with_anisotropic_roughness(0.1, 0.5)),
+            true,
+        );
+        assert!(albedo < 1.0, "albedo {}", albedo);
+    }
+
+    #[test]
+    fn test_tangent_orients_anisotropic_highlight() {
+        let metal: Arc<dyn Material> =
+            Arc::new(Conductor::silver(0.1).with_anisotropic_roughness(0.05, 0.4));
+        // Mirror direction tilted away along x or along z.
+        let glossy = |tangent: Vec3, tilt: Vec3| {
+            let (r_in, mut rec) = hit_from_above(metal.clone(), true);
+            rec.tangent = tangent;
+            rec.bitangent = rec.normal.cross(tangent);
+            let mut srec = ScatterRecord::default();
+            assert!(metal.scatter(&r_in, &rec, &mut srec));
+            let mirror = Vec3::reflect(&r_in.direction.unit_vector(), &rec.normal);
+            let d = (mirror + 0.3 * tilt).unit_vector();
+            metal.eval(&r_in, &rec, &srec, &Ray::new(rec.p, d, 0.0)).x
+        };
+        let (x, z) = (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        // The highlight is narrow along the tangent and stretched along the bitangent.
+        assert!(glossy(x, z) > 10.0 * glossy(x, x));
+        assert!(glossy(z, x) > 10.0 * glossy(z, z));
+    }
+
+    #[test]
+    fn test_instancing_turns_the_highlight_with_the_surface() {
+        let metal: Arc<dyn Material> =
+            Arc::new(Conductor::silver(0.1).with_anisotropic_roughness(0.05, 0.4));
+        let floor: Arc<dyn Hittable> = Arc::new(XzRect::new(-1.0, 1.0, -1.0, 1.0, 0.0, metal));
+        let turned = RotateY::new(floor.clone(), 90.0);
+        // Mirror direction tilted away along x or along z.
+        let glossy = |surface: &dyn Hittable, tilt: Vec3| {
+            let r_in = Ray::new(Point3::new(-0.3, 1.0, -0.1), Vec3::new(0.3, -1.0, 0.1), 0.0);
+            let rec = surface.hit(&r_in, 0.001, f64::INFINITY).unwrap();
+            let mut srec = ScatterRecord::default();
+            assert!(rec.mat_ptr.scatter(&r_in, &rec, &mut srec));
+            let mirror = Vec3::reflect(&r_in.direction.unit_vector(), &rec.normal);
+            let d = (mirror + 0.3 * tilt).unit_vector();
+            rec.mat_ptr
+                .eval(&r_in, &rec, &srec, &Ray::new(rec.p, d, 0.0))
+                .x
+        };
+        let (x, z) = (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(glossy(floor.as_ref(), z) > 10.0 * glossy(floor.as_ref(), x));
+        assert!(glossy(&turned, x) > 10.0 * glossy(&turned, z));
+    }
+
+    #[test]
+    fn test_rough_dielectric_conserves_energy() {
+        for front_face in [true, false] {
+            let glass = RoughDielectric::new(1.5, 0.5).with_anisotropic_roughness(0.4, 0.7);
+            let albedo = albedo_two_ways(Arc::new(glass), front_face);
+            // Single scattering loses a little energy, but never creates any.
+            assert!(albedo > 0.8 && albedo < 1.01, "albedo {}", albedo);
+        }
+    }
+
+    #[test]
+    fn test_oren_nayar_white_furnace() {
+        let white = Arc::new(SolidColor::from_rgb(1.0, 1.0, 1.0));
+        let smooth = albedo_two_ways(Arc::new(OrenNayar::new(white.clone(), 0.0)), true);
+        assert!((smooth - 1.0).abs() < 0.01, "albedo {}", smooth);
+        for sigma in [20.0, 45.0, 90.0] {
+            let rough = Arc::new(OrenNayar::new(white.clone(), sigma));
+            let albedo = albedo_two_ways(rough, true);
+            // Light bouncing between the grooves is left out, so rough surfaces lose some.
+            assert!(albedo > 0.5 && albedo < 1.01, "albedo {}", albedo);
+        }
+    }
+
+    #[test]
+    fn test_velvet_white_furnace() {
+        let white = Arc::new(SolidColor::from_rgb(1.0, 1.0, 1.0));
+        for roughness in [0.1, 0.4, 1.0] {
+            let velvet = Velvet::new(white.clone(), white.clone(), roughness);
+            let albedo = albedo_two_ways(Arc::new(velvet), true);
+            assert!(albedo > 0.9 && albedo < 1.01, "albedo {}", albedo);
+        }
+    }
+
+    #[test]
+    fn test_coat_dims_base_without_creating_energy() {
+        let white = Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+            1.0, 1.0, 1.0,
+        ))));
+        let albedo = albedo_two_ways(Arc::new(Coated::new(white, 1.5)), true);
+        assert!(albedo > 0.85 && albedo < 1.0, "albedo {}", albedo);
+
+        let glass: Arc<dyn Material> = Arc::new(Dielectric::new(1.5).with_priority(2));
+        let coated = Coated::new(glass.clone(), 1.5);
+        assert_eq!(
+            coated.medium().map(|m| (m.ior, m.priority)),
+            glass.medium().map(|m| (m.ior, m.priority))
+        );
+    }
+
+    #[test]
+    fn test_thin_film_splits_light_between_reflection_and_refraction() {
+        seed_random(20);
+        let film = ThinFilm::new(500.0, 1.33);
+        let bubble: Arc<dyn Material> = Arc::new(Dielectric::new(1.0).with_thin_film(film));
+        let (r_in, rec) = hit_from_above(bubble.clone(), true);
+        let cos_theta = rec.normal.dot(-r_in.direction.unit_vector());
+        let r = reflectance_to_rgb(|lambda| film.reflectance(cos_theta, 1.0, 1.0, lambda));
+        let r = Color::new(
+            r.x.clamp(0.0, 1.0),
+            r.y.clamp(0.0, 1.0),
+            r.z.clamp(0.0, 1.0),
+        );
+
+        // Reflections carry r / p and refractions (1 - r) / (1 - p), so the two average
+        // to the film's reflectance and its complement in every channel.
+        let n = 100_000;
+        let (mut reflected, mut refracted) = (Color::default(), Color::default());
+        for _ in 0..n {
+            let mut srec = ScatterRecord::default();
+            assert!(bubble.scatter(&r_in, &rec, &mut srec));
+            assert!(srec.is_specular);
+            if srec.specular_ray.direction.dot(rec.normal) > 0.0 {
+                reflected += srec.attenuation / n as f64;
+            } else {
+                refracted += srec.attenuation / n as f64;
+            }
+        }
+        let white = Color::new(1.0, 1.0, 1.0);
+        assert!(
+            (reflected - r).length() < 0.01,
+            "{:?} vs {:?}",
+            reflected,
+            r
+        );
+        assert!((refracted - (white - r)).length() < 0.01);
+        assert!((reflected + refracted - white).length() < 0.01);
+        // The film tints the reflection rather than graying it.
+        assert!((r.x - r.z).abs() > 0.05, "{:?}", r);
+
+        // A high index film seen head-on reflects a red below the sRGB gamut, which must
+        // not turn into negative light either way.
+        let film = ThinFilm::new(250.0, 2.4);
+        assert!(reflectance_to_rgb(|lambda| film.reflectance(1.0, 1.0, 1.0, lambda)).x < 0.0);
+        let bubble: Arc<dyn Material> = Arc::new(Dielectric::new(1.0).with_thin_film(film));
+        let (_, rec) = hit_from_above(bubble.clone(), true);
+        let r_in = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let mut total = Color::default();
+        for _ in 0..n {
+            let mut srec = ScatterRecord::default();
+            assert!(bubble.scatter(&r_in, &rec, &mut srec));
+            let a = srec.attenuation;
+            assert!(a.x >= 0.0 && a.y >= 0.0 && a.z >= 0.0, "{:?}", a);
+            total += a / n as f64;
+        }
+        assert!((total - white).length() < 0.02, "{:?}", total);
+    }
 }
@@ -0,0 +1,293 @@
+use crate::rtweekend::PI;
+use crate::vec3::{Color, Vec3};
+use std::ops::{Add, Div, Mul, Sub};
+
+// The Trowbridge-Reitz (GGX) microfacet distribution. Directions are in the local
+// shading frame, with the macro-surface normal along +z.
+#[derive(Clone, Copy)]
+pub struct TrowbridgeReitz {
+    alpha_x: f64,
+    alpha_y: f64,
+}
+
+impl TrowbridgeReitz {
+    pub fn new(alpha_x: f64, alpha_y: f64) -> Self {
+        Self {
+            alpha_x: alpha_x.max(1e-4),
+            alpha_y: alpha_y.max(1e-4),
+        }
+    }
+
+    // Maps artist-friendly roughness in [0, 1] to alpha; the square keeps the response
+    // roughly linear in perceived blur.
+    pub fn from_roughness(roughness_u: f64, roughness_v: f64) -> Self {
+        Self::new(roughness_u * roughness_u, roughness_v * roughness_v)
+    }
+
+    // Below this the surface behaves as a perfect mirror and is better treated as specular.
+    pub fn effectively_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < 1e-3
+    }
+
+    pub fn d(&self, wm: Vec3) -> f64 {
+        let cos2_theta = wm.z * wm.z;
+        if cos2_theta < 1e-16 {
+            return 0.0;
+        }
+        let e = (wm.x * wm.x / (self.alpha_x * self.alpha_x)
+            + wm.y * wm.y / (self.alpha_y * self.alpha_y))
+            / cos2_theta;
+        1.0 / (PI * self.alpha_x * self.alpha_y * cos2_theta * cos2_theta * (1.0 + e) * (1.0 + e))
+    }
+
+    fn lambda(&self, w: Vec3) -> f64 {
+        let cos2_theta = w.z * w.z;
+        if cos2_theta == 0.0 {
+            return f64::INFINITY;
+        }
+        let alpha2_tan2_theta = (w.x * w.x * self.alpha_x * self.alpha_x
+            + w.y * w.y * self.alpha_y * self.alpha_y)
+            / cos2_theta;
+        ((1.0 + alpha2_tan2_theta).sqrt() - 1.0) / 2.0
+    }
+
+    pub fn g1(&self, w: Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    // Height-correlated masking and shadowing.
+    pub fn g(&self, wo: Vec3, wi: Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    // Density of the visible normals seen from `w`.
+    pub fn d_visible(&self, w: Vec3, wm: Vec3) -> f64 {
+        if w.z == 0.0 {
+            return 0.0;
+        }
+        self.g1(w) / w.z.abs() * self.d(wm) * w.dot(wm).abs()
+    }
+
+    // Samples a normal from the distribution of visible normals (Heitz 2018).
+    pub fn sample_wm(&self, w: Vec3, u1: f64, u2: f64) -> Vec3 {
+        // Stretch the view direction into the hemisphere configuration.
+        let mut wh = Vec3::new(self.alpha_x * w.x, self.alpha_y * w.y, w.z).unit_vector();
+        if wh.z < 0.0 {
+            wh = -wh;
+        }
+        let t1 = if wh.z < 0.99999 {
+            Vec3::new(0.0, 0.0, 1.0).cross(wh).unit_vector()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = wh.cross(t1);
+
+        // A point on the disk, warped toward the visible half of the projected hemisphere.
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let px = r * phi.cos();
+        let h = (1.0 - px * px).sqrt();
+        let s = (1.0 + wh.z) / 2.0;
+        let py = (1.0 - s) * h + s * r * phi.sin();
+        let pz = (1.0 - px * px - py * py).max(0.0).sqrt();
+
+        let nh = px * t1 + py * t2 + pz * wh;
+        Vec3::new(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(1e-6)).unit_vector()
+    }
+}
+
+// Unpolarized Fresnel reflectance of a dielectric interface, where `eta` is the
+// transmitted over the incident index of refraction.
+pub fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let mut cos_theta_i = cos_theta_i.clamp(-1.0, 1.0);
+    let mut eta = eta;
+    if cos_theta_i < 0.0 {
+        eta = 1.0 / eta;
+        cos_theta_i = -cos_theta_i;
+    }
+    let sin2_theta_t = (1.0 - cos_theta_i * cos_theta_i) / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+    let r_parl = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perp = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    (r_parl * r_parl + r_perp * r_perp) / 2.0
+}
+
+// Fresnel reflectance of a conductor with complex index of refraction `eta + i k`,
+// evaluated per color channel.
+pub fn fresnel_conductor(cos_theta_i: f64, eta: Color, k: Color) -> Color {
+    let channel = |eta: f64, k: f64| {
+        let cos_theta_i = cos_theta_i.clamp(0.0, 1.0);
+        let eta = Complex::new(eta, k);
+        let sin2_theta_t = Complex::from(1.0 - cos_theta_i * cos_theta_i) / (eta * eta);
+        let cos_theta_t = (Complex::from(1.0) - sin2_theta_t).sqrt();
+        let cos_theta_i = Complex::from(cos_theta_i);
+        let r_parl = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+        let r_perp = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+        (r_parl.norm() + r_perp.norm()) / 2.0
+    };
+    Color::new(
+        channel(eta.x, k.x),
+        channel(eta.y, k.y),
+        channel(eta.z, k.z),
+    )
+}
+
+// Refracts `wi` through a surface with normal `n`, which may face either way. Returns
+// None on total internal reflection.
+pub fn refract(wi: Vec3, n: Vec3, eta: f64) -> Option<Vec3> {
+    let mut cos_theta_i = n.dot(wi);
+    let (mut eta, mut n) = (eta, n);
+    if cos_theta_i < 0.0 {
+        eta = 1.0 / eta;
+        cos_theta_i = -cos_theta_i;
+        n = -n;
+    }
+    let sin2_theta_t = (1.0 - cos_theta_i * cos_theta_i).max(0.0) / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        return None;
+    }
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+    Some(-wi / eta + (cos_theta_i / eta - cos_theta_t) * n)
+}
+
+// The microfacet normal that takes local `wo` (above the surface) to `wi`, by reflection
+// when both are on the same side and by refraction otherwise.
+pub fn generalized_half_vector(wo: Vec3, wi: Vec3, eta: f64) -> Option<Vec3> {
+    if wo.z <= 0.0 || wi.z == 0.0 {
+        return None;
+    }
+    let etap = if wi.z > 0.0 { 1.0 } else { eta };
+    let wm = wi * etap + wo;
+    if wm.length_squared() == 0.0 {
+        return None;
+    }
+    let mut wm = wm.unit_vector();
+    if wm.z < 0.0 {
+        wm = -wm;
+    }
+    // Microfacets facing away from either direction contribute nothing.
+    if wm.dot(wi) * wi.z < 0.0 || wm.dot(wo) * wo.z < 0.0 {
+        return None;
+    }
+    Some(wm)
+}
+
+#[derive(Clone, Copy)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    fn norm(self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    fn sqrt(self) -> Self {
+        let n = self.norm().sqrt();
+        if n == 0.0 {
+            return Self::from(0.0);
+        }
+        let t1 = (0.5 * (n + self.re.abs())).sqrt();
+        let t2 = 0.5 * self.im / t1;
+        if self.re >= 0.0 {
+            Self::new(t1, t2)
+        } else {
+            Self::new(t2.abs(), t1.copysign(self.im))
+        }
+    }
+}
+
+impl From<f64> for Complex {
+    fn from(re: f64) -> Self {
+        Self::new(re, 0.0)
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, o: Self) -> Self {
+        Self::new(self.re + o.re, self.im + o.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, o: Self) -> Self {
+        Self::new(self.re - o.re, self.im - o.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, o: Self) -> Self {
+        Self::new(
+            self.re * o.re - self.im * o.im,
+            self.re * o.im + self.im * o.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Self;
+
+    fn div(self, o: Self) -> Self {
+        let scale = 1.0 / o.norm();
+        Self::new(
+            scale * (self.re * o.re + self.im * o.im),
+            scale * (self.im * o.re - self.re * o.im),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rtweekend::random_double;
+
+    #[test]
+    fn test_visible_normals_match_their_density() {
+        // The projected area of the microsurface equals that of the macro-surface, so the
+        // visible normal density integrates to one over the hemisphere.
+        let distribution = TrowbridgeReitz::new(0.3, 0.6);
+        let wo = Vec3::new(0.5, -0.2, 0.7).unit_vector();
+        let n = 200_000;
+        let sum: f64 = (0..n)
+            .map(|_| {
+                let mut w = Vec3::random_unit_vector();
+                if w.z < 0.0 {
+                    w = -w;
+                }
+                distribution.d_visible(wo, w)
+            })
+            .sum();
+        assert!((sum * 2.0 * PI / n as f64 - 1.0).abs() < 0.05);
+
+        // Sampled normals face the viewer.
+        for _ in 0..1000 {
+            let wm = distribution.sample_wm(wo, random_double(), random_double());
+            assert!(wm.z > 0.0 && wm.dot(wo) > -1e-9);
+        }
+    }
+
+    #[test]
+    fn test_fresnel() {
+        // Glass at normal incidence reflects 4%.
+        assert!((fresnel_dielectric(1.0, 1.5) - 0.04).abs() < 1e-9);
+        // Total internal reflection leaving the glass at a grazing angle.
+        assert_eq!(fresnel_dielectric(-0.1, 1.5), 1.0);
+        // With no absorption the conductor formula reduces to the dielectric one.
+        let f = fresnel_conductor(0.6, Color::new(1.5, 1.5, 1.5), Color::default());
+        assert!((f.x - fresnel_dielectric(0.6, 1.5)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::aabb::{Aabb, surrounding_box};
+use crate::hittable::{HitRecord, Hittable, get_sphere_tangents, get_sphere_uv};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use std::sync::Arc;
+
+pub struct MovingSphere {
+    pub center0: Point3,
+    pub center1: Point3,
+    pub time0: f64,
+    pub time1: f64,
+    pub radius: f64,
+    pub mat_ptr: Arc<dyn Material>,
+}
+
+impl MovingSphere {
+    pub fn new(
+        center0: Point3,
+        center1: Point3,
+        time0: f64,
+        time1: f64,
+        radius: f64,
+        mat_ptr: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            center0,
+            center1,
+            time0,
+            time1,
+            radius,
+            mat_ptr,
+        }
+    }
+
+    pub fn center(&self, time: f64) -> Point3 {
+        if self.time1 == self.time0 {
+            return self.center0;
+        }
+        self.center0
+            + ((time - self.time0) / (self.time1 - self.time0)) * (self.center1 - self.center0)
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let center = self.center(r.time);
+        let oc = r.origin - center;
+        let a = r.direction.length_squared();
+        let half_b = oc.dot(r.direction);
+        let c = oc.length_squared() - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+
+        if discriminant < 0.0 {
+            return None;
+        }
+        let sqrtd = discriminant.sqrt();
+
+        // Find the nearest root that lies in the acceptable range.
+        let mut root = (-half_b - sqrtd) / a;
+        if root < t_min || t_max < root {
+            root = (-half_b + sqrtd) / a;
+            if root < t_min || t_max < root {
+                return None;
+            }
+        }
+
+        let p = r.at(root);
+        let outward_normal = (p - center) / self.radius;
+        let (u, v) = get_sphere_uv(&outward_normal);
+        let (tangent, bitangent) = get_sphere_tangents(&outward_normal, self.radius);
+        let mut rec = HitRecord {
+            p,
+            t: root,
+            u,
+            v,
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            tangent,
+            bitangent,
+            front_face: false,
+            mat_ptr: Arc::clone(&self.mat_ptr),
+        };
+        rec.set_face_normal(r, outward_normal);
+        Some(rec)
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
+        let radius = Vec3::new(self.radius, self.radius, self.radius);
+        let box0 = Aabb::from_points(self.center(t0) - radius, self.center(t0) + radius);
+        let box1 = Aabb::from_points(self.center(t1) - radius, self.center(t1) + radius);
+        Some(surrounding_box(&box0, &box1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::texture::SolidColor;
+
+    fn moving_sphere() -> MovingSphere {
+        MovingSphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 2.0, 0.0),
+            0.0,
+            1.0,
+            0.5,
+            Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+                0.5, 0.5, 0.5,
+            )))),
+        )
+    }
+
+    #[test]
+    fn test_center_interpolates_between_keyframes() {
+        let sphere = moving_sphere();
+        assert_eq!(sphere.center(0.0), Point3::new(0.0, 0.0, 0.0));
+        assert_eq!(sphere.center(0.5), Point3::new(0.0, 1.0, 0.0));
+        assert_eq!(sphere.center(1.0), Point3::new(0.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn test_hit_depends_on_ray_time() {
+        let sphere = moving_sphere();
+        let origin = Point3::new(0.0, 2.0, -5.0);
+        let direction = Vec3::new(0.0, 0.0, 1.0);
+        assert!(
+            sphere
+                .hit(&Ray::new(origin, direction, 0.0), 0.001, f64::INFINITY)
+                .is_none()
+        );
+        assert!(
+            sphere
+                .hit(&Ray::new(origin, direction, 1.0), 0.001, f64::INFINITY)
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_bounding_box_encloses_whole_motion() {
+        let bbox = moving_sphere().bounding_box(0.0, 1.0).unwrap();
+        assert_eq!(bbox.min, Point3::new(-0.5, -0.5, -0.5));
+        assert_eq!(bbox.max, Point3::new(0.5, 2.5, 0.5));
+    }
+}
@@ -0,0 +1,149 @@
+use crate::material::Material;
+use crate::vec3::Color;
+
+const MAX_DEPTH: usize = 8;
+
+// The interior of a dielectric as seen by a path travelling through it. Where two
+// dielectrics overlap, the one with the higher priority owns the overlap.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Medium {
+    pub id: usize, // from `medium_id` of the material that owns it
+    pub ior: f64,
+    pub absorption: Color, // per unit length
+    pub scattering: Color, // per unit length
+    pub priority: u32,
+}
+
+impl Medium {
+    pub fn extinction(&self) -> Color {
+        self.absorption + self.scattering
+    }
+}
+
+// Identifies the medium behind a material by the material's address, so every object
+// sharing one material is treated as one medium, even when wrapped in another material.
+pub fn medium_id(material: &dyn Material) -> usize {
+    material as *const dyn Material as *const () as usize
+}
+
+// The dielectrics a path is currently inside, innermost last.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MediumStack {
+    entries: [Medium; MAX_DEPTH],
+    len: usize,
+}
+
+impl MediumStack {
+    // The medium the path is travelling through: the highest priority one, and of those
+    // the most recently entered.
+    pub fn current(&self) -> Option<&Medium> {
+        self.current_without(None)
+    }
+
+    pub fn ior(&self) -> f64 {
+        self.current().map_or(1.0, |m| m.ior)
+    }
+
+    // The index of refraction beyond the surface of medium `id` when leaving it.
+    pub fn ior_without(&self, id: usize) -> f64 {
+        self.current_without(Some(id)).map_or(1.0, |m| m.ior)
+    }
+
+    fn current_without(&self, id: Option<usize>) -> Option<&Medium> {
+        self.entries[..self.len]
+            .iter()
+            .filter(|medium| Some(medium.id) != id)
+            .reduce(|a, b| if b.priority >= a.priority { b } else { a })
+    }
+
+    // Whether crossing the surface of `medium` changes the medium the path is in.
+    // Surfaces inside a higher priority medium are not real interfaces.
+    pub fn is_real_interface(&self, medium: &Medium, entering: bool) -> bool {
+        if entering {
+            self.current().is_none_or(|c| medium.priority >= c.priority)
+        } else {
+            self.current_without(Some(medium.id))
+                .is_none_or(|c| medium.priority >= c.priority)
+        }
+    }
+
+    pub fn cross(&mut self, medium: Medium, entering: bool) {
+        if entering {
+            if self.len < MAX_DEPTH {
+                self.entries[self.len] = medium;
+                self.len += 1;
+            }
+        } else if let Some(i) = self.entries[..self.len]
+            .iter()
+            .rposition(|e| e.id == medium.id)
+        {
+            self.entries.copy_within(i + 1..self.len, i);
+            self.len -= 1;
+        }
+    }
+
+    // Beer-Lambert attenuation over `distance` through the current medium.
+    pub fn transmittance(&self, distance: f64) -> Color {
+        let Some(medium) = self.current() else {
+            return Color::new(1.0, 1.0, 1.0);
+        };
+        let a = medium.extinction();
+        let t = |sigma: f64| {
+            if sigma > 0.0 {
+                (-sigma * distance).exp()
+            } else {
+                1.0
+            }
+        };
+        Color::new(t(a.x), t(a.y), t(a.z))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glass_of_water() {
+        let glass = Medium {
+            id: 1,
+            ior: 1.5,
+            absorption: Color::default(),
+            scattering: Color::default(),
+            priority: 2,
+        };
+        let water = Medium {
+            id: 2,
+            ior: 1.33,
+            absorption: Color::new(0.5, 0.1, 0.0),
+            scattering: Color::default(),
+            priority: 1,
+        };
+        let mut stack = MediumStack::default();
+
+        // Into the glass wall, then into the water that overlaps the wall: not a real
+        // interface, since the glass owns the overlap.
+        assert!(stack.is_real_interface(&glass, true));
+        stack.cross(glass, true);
+        assert!(!stack.is_real_interface(&water, true));
+        stack.cross(water, true);
+        assert_eq!(stack.ior(), 1.5);
+
+        // Out of the glass and into the water: a glass-water interface.
+        assert!(stack.is_real_interface(&glass, false));
+        assert_eq!(stack.ior_without(1), 1.33);
+        stack.cross(glass, false);
+        assert_eq!(stack.current(), Some(&water));
+
+        let t = stack.transmittance(2.0);
+        assert!((t.x - (-1.0f64).exp()).abs() < 1e-12);
+        assert_eq!(t.z, 1.0);
+
+        stack.cross(water, false);
+        assert_eq!(stack.ior(), 1.0);
+        assert_eq!(
+            stack.transmittance(f64::INFINITY),
+            Color::new(1.0, 1.0, 1.0)
+        );
+    }
+}
@@ -0,0 +1,665 @@
+use crate::hittable_list::HittableList;
+use crate::material::{Dielectric, Lambertian, Material, Metal};
+use crate::principled::Principled;
+use crate::texture::{ImageTexture, SolidColor, Texture};
+use crate::triangle::{MeshData, TriangleMesh};
+use crate::vec3::{Color, Point3, Vec3};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::SplitWhitespace;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct ObjError {
+    pub path: PathBuf,
+    pub line: usize, // 1-based, 0 when the error is not tied to a line
+    pub message: String,
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}: {}", self.path.display(), self.message)
+        } else {
+            write!(f, "{}:{}: {}", self.path.display(), self.line, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+// The faces of one group that share a material, as a single indexed mesh.
+pub struct ObjGroup {
+    pub name: String,
+    pub material: Option<String>,
+    pub mesh: MeshData,
+}
+
+pub fn load_obj(path: impl AsRef<Path>) -> Result<HittableList, ObjError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|e| ObjError {
+        path: path.to_path_buf(),
+        line: 0,
+        message: e.to_string(),
+    })?;
+
+    let mut world = HittableList::new();
+    for group in parse_obj(&source, path)? {
+        world.add(Arc::new(TriangleMesh::new(group.mesh)));
+    }
+    Ok(world)
+}
+
+// Parses OBJ text. `path` names the file in error messages and is the base for mtllib.
+pub fn parse_obj(source: &str, path: &Path) -> Result<Vec<ObjGroup>, ObjError> {
+    let mut parser = ObjParser {
+        path,
+        line: 0,
+        positions: Vec::new(),
+        normals: Vec::new(),
+        uvs: Vec::new(),
+        materials: HashMap::new(),
+        group: "default".to_string(),
+        material: None,
+        builders: Vec::new(),
+    };
+
+    for (i, line) in source.lines().enumerate() {
+        parser.line = i + 1;
+        let line = line.split('#').next().unwrap_or("");
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+
+        match keyword {
+            "v" => {
+                let p = parser.parse_vec3(&mut tokens, "vertex")?;
+                parser.positions.push(p);
+            }
+            "vn" => {
+                let n = parser.parse_vec3(&mut tokens, "normal")?;
+                parser.normals.push(n);
+            }
+            "vt" => {
+                let u = parser.parse_float(tokens.next(), "texture coordinate")?;
+                let v = match tokens.next() {
+                    Some(token) => parser.parse_float(Some(token), "texture coordinate")?,
+                    None => 0.0,
+                };
+                parser.uvs.push((u, v));
+            }
+            "f" => parser.parse_face(tokens)?,
+            "g" | "o" => {
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                parser.group = if name.is_empty() {
+                    "default".to_string()
+                } else {
+                    name
+                };
+            }
+            "usemtl" => {
+                let name = tokens
+                    .next()
+                    .ok_or_else(|| parser.error("usemtl needs a material name"))?;
+                if !parser.materials.contains_key(name) {
+                    return Err(parser.error(&format!("unknown material '{}'", name)));
+                }
+                parser.material = Some(name.to_string());
+            }
+            "mtllib" => {
+                for file in tokens {
+                    let mtl_path = path.parent().unwrap_or(Path::new("")).join(file);
+                    let mtl_source = fs::read_to_string(&mtl_path).map_err(|e| {
+                        parser.error(&format!("cannot read {}: {}", mtl_path.display(), e))
+                    })?;
+                    parser.materials.extend(parse_mtl(&mtl_source, &mtl_path)?);
+                }
+            }
+            // Smoothing groups, lines, points and other statements do not affect rendering.
+            _ => {}
+        }
+    }
+
+    Ok(parser.finish())
+}
+
+struct ObjParser<'a> {
+    path: &'a Path,
+    line: usize,
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<(f64, f64)>,
+    materials: HashMap<String, Arc<dyn Material>>,
+    group: String,
+    material: Option<String>,
+    builders: Vec<GroupBuilder>,
+}
+
+// A face vertex as (position, texcoord, normal) indices into the global buffers.
+type VertexKey = (usize, Option<usize>, Option<usize>);
+
+struct GroupBuilder {
+    name: String,
+    material: Option<String>,
+    vertices: Vec<VertexKey>,
+    lookup: HashMap<VertexKey, usize>,
+    indices: Vec<[usize; 3]>,
+}
+
+impl ObjParser<'_> {
+    fn error(&self, message: &str) -> ObjError {
+        ObjError {
+            path: self.path.to_path_buf(),
+            line: self.line,
+            message: message.to_string(),
+        }
+    }
+
+    fn parse_float(&self, token: Option<&str>, what: &str) -> Result<f64, ObjError> {
+        let token = token.ok_or_else(|| self.error(&format!("missing {} component", what)))?;
+        token
+            .parse()
+            .map_err(|_| self.error(&format!("invalid number '{}' in {}", token, what)))
+    }
+
+    fn parse_vec3(&self, tokens: &mut SplitWhitespace, what: &str) -> Result<Vec3, ObjError> {
+        Ok(Vec3::new(
+            self.parse_float(tokens.next(), what)?,
+            self.parse_float(tokens.next(), what)?,
+            self.parse_float(tokens.next(), what)?,
+        ))
+    }
+
+    // Resolves a 1-based (or negative, relative) OBJ index against `count` elements.
+    fn resolve_index(&self, token: &str, count: usize, what: &str) -> Result<usize, ObjError> {
+        let index: i64 = token
+            .parse()
+            .map_err(|_| self.error(&format!("invalid {} index '{}'", what, token)))?;
+        let resolved = if index > 0 {
+            index - 1
+        } else {
+            count as i64 + index
+        };
+        if index == 0 || resolved < 0 || resolved >= count as i64 {
+            return Err(self.error(&format!(
+                "{} index {} out of range (have {})",
+                what, index, count
+            )));
+        }
+        Ok(resolved as usize)
+    }
+
+    fn parse_vertex(&self, token: &str) -> Result<VertexKey, ObjError> {
+        let mut parts = token.split('/');
+        let v = self.resolve_index(parts.next().unwrap_or(""), self.positions.len(), "vertex")?;
+        let vt = match parts.next() {
+            Some("") | None => None,
+            Some(t) => Some(self.resolve_index(t, self.uvs.len(), "texture coordinate")?),
+        };
+        let vn = match parts.next() {
+            Some("") | None => None,
+            Some(n) => Some(self.resolve_index(n, self.normals.len(), "normal")?),
+        };
+        Ok((v, vt, vn))
+    }
+
+    fn parse_face(&mut self, tokens: SplitWhitespace) -> Result<(), ObjError> {
+        let keys = tokens
+            .map(|token| self.parse_vertex(token))
+            .collect::<Result<Vec<_>, _>>()?;
+        if keys.len() < 3 {
+            return Err(self.error(&format!(
+                "face needs at least 3 vertices, got {}",
+                keys.len()
+            )));
+        }
+
+        let builder = self.current_builder();
+        let local: Vec<usize> = keys.iter().map(|key| builder.vertex(*key)).collect();
+
+        // Convex polygons are triangulated as a fan around the first vertex.
+        for i in 1..local.len() - 1 {
+            builder.indices.push([local[0], local[i], local[i + 1]]);
+        }
+        Ok(())
+    }
+
+    fn current_builder(&mut self) -> &mut GroupBuilder {
+        let position = self
+            .builders
+            .iter()
+            .position(|b| b.name == self.group && b.material == self.material);
+        match position {
+            Some(i) => &mut self.builders[i],
+            None => {
+                self.builders.push(GroupBuilder {
+                    name: self.group.clone(),
+                    material: self.material.clone(),
+                    vertices: Vec::new(),
+                    lookup: HashMap::new(),
+                    indices: Vec::new(),
+                });
+                self.builders.last_mut().unwrap()
+            }
+        }
+    }
+
+    fn finish(self) -> Vec<ObjGroup> {
+        let default_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::new(
+            SolidColor::from_rgb(0.5, 0.5, 0.5),
+        )));
+
+        self.builders
+            .into_iter()
+            .filter(|b| !b.indices.is_empty())
+            .map(|b| {
+                let positions = b.vertices.iter().map(|k| self.positions[k.0]).collect();
+                // Attributes are only kept when every vertex of the group provides them.
+                let uvs = b
+                    .vertices
+                    .iter()
+                    .map(|k| k.1.map(|i| self.uvs[i]))
+                    .collect::<Option<Vec<_>>>();
+                let normals = b
+                    .vertices
+                    .iter()
+                    .map(|k| k.2.map(|i| self.normals[i]))
+                    .collect::<Option<Vec<_>>>();
+                let mat_ptr = match &b.material {
+                    Some(name) => Arc::clone(&self.materials[name]),
+                    None => Arc::clone(&default_material),
+                };
+
+                ObjGroup {
+                    name: b.name,
+                    material: b.material,
+                    mesh: MeshData {
+                        positions,
+                        normals,
+                        uvs,
+                        indices: b.indices,
+                        mat_ptr,
+                    },
+                }
+            })
+            .collect()
+    }
+}
+
+impl GroupBuilder {
+    fn vertex(&mut self, key: VertexKey) -> usize {
+        *self.lookup.entry(key).or_insert_with(|| {
+            self.vertices.push(key);
+            self.vertices.len() - 1
+        })
+    }
+}
+
+struct MtlDesc {
+    kd: Color,
+    ks: Color,
+    ns: f64,
+    ni: f64,
+    d: f64,
+    illum: u32,
+    map_kd: Option<Arc<dyn Texture>>,
+    // The PBR extension: roughness, metallic, sheen, clearcoat and clearcoat roughness.
+    pr: Option<f64>,
+    pm: Option<f64>,
+    ps: Option<f64>,
+    pc: Option<f64>,
+    pcr: Option<f64>,
+    map_pr: Option<Arc<dyn Texture>>,
+    map_pm: Option<Arc<dyn Texture>>,
+}
+
+impl Default for MtlDesc {
+    fn default() -> Self {
+        Self {
+            kd: Color::new(0.8, 0.8, 0.8),
+            ks: Color::default(),
+            ns: 0.0,
+            ni: 1.5,
+            d: 1.0,
+            illum: 2,
+            map_kd: None,
+            pr: None,
+            pm: None,
+            ps: None,
+            pc: None,
+            pcr: None,
+            map_pr: None,
+            map_pm: None,
+        }
+    }
+}
+
+impl MtlDesc {
+    // Maps the Phong-style description onto the closest built-in material.
+    fn to_material(&self) -> Arc<dyn Material> {
+        let max = |c: Color| c.x.max(c.y).max(c.z);
+
+        if self.is_pbr() {
+            return Arc::new(self.to_principled());
+        }
+
+        if self.d < 1.0 || matches!(self.illum, 4 | 6 | 7 | 9) {
+            return Arc::new(Dielectric::new(self.ni));
+        }
+        if max(self.ks) > max(self.kd) {
+            // Convert the Phong exponent to an approximate roughness.
+            let fuzz = (2.0 / (self.ns + 2.0)).sqrt();
+            return Arc::new(Metal::new(self.ks, fuzz));
+        }
+        match &self.map_kd {
+            Some(texture) => Arc::new(Lambertian::new(Arc::clone(texture))),
+            None => Arc::new(Lambertian::new(Arc::new(SolidColor::new(self.kd)))),
+        }
+    }
+
+    fn is_pbr(&self) -> bool {
+        [self.pr, self.pm, self.ps, self.pc, self.pcr]
+            .iter()
+            .any(Option::is_some)
+            || self.map_pr.is_some()
+            || self.map_pm.is_some()
+    }
+
+    fn to_principled(&self) -> Principled {
+        let constant = |value: f64| -> Arc<dyn Texture> {
+            Arc::new(SolidColor::from_rgb(value, value, value))
+        };
+        let parameter = |map: &Option<Arc<dyn Texture>>, value: Option<f64>, default: f64| {
+            map.clone()
+                .unwrap_or_else(|| constant(value.unwrap_or(default)))
+        };
+
+        // Principled's specular is the normal-incidence reflectance over 0.08.
+        let f0 = ((self.ni - 1.0) / (self.ni + 1.0)).powi(2);
+        let base_color = self
+            .map_kd
+            .clone()
+            .unwrap_or_else(|| Arc::new(SolidColor::new(self.kd)));
+        Principled::new(base_color)
+            .with_metallic(parameter(&self.map_pm, self.pm, 0.0))
+            .with_roughness(parameter(&self.map_pr, self.pr, 0.5))
+            .with_specular(constant(f0 / 0.08))
+            .with_sheen(constant(self.ps.unwrap_or(0.0)))
+            .with_clearcoat(
+                constant(self.pc.unwrap_or(0.0)),
+                constant(self.pcr.unwrap_or(0.03)),
+            )
+            .with_transmission(constant(1.0 - self.d))
+    }
+}
+
+// Parses MTL text. `path` names the file in error messages and is the base for textures.
+pub fn parse_mtl(
+    source: &str,
+    path: &Path,
+) -> Result<HashMap<String, Arc<dyn Material>>, ObjError> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlDesc)> = None;
+
+    for (i, line) in source.lines().enumerate() {
+        let error = |message: String| ObjError {
+            path: path.to_path_buf(),
+            line: i + 1,
+            message,
+        };
+        let float = |token: Option<&str>, what: &str| -> Result<f64, ObjError> {
+            let token = token.ok_or_else(|| error(format!("missing value for {}", what)))?;
+            token
+                .parse()
+                .map_err(|_| error(format!("invalid number '{}' in {}", token, what)))
+        };
+
+        let line = line.split('#').next().unwrap_or("");
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+
+        if keyword == "newmtl" {
+            if let Some((name, desc)) = current.take() {
+                materials.insert(name, desc.to_material());
+            }
+            let name = tokens
+                .next()
+                .ok_or_else(|| error("newmtl needs a material name".to_string()))?;
+            current = Some((name.to_string(), MtlDesc::default()));
+            continue;
+        }
+
+        let Some((_, desc)) = current.as_mut() else {
+            return Err(error(format!("'{}' before any newmtl", keyword)));
+        };
+        match keyword {
+            "Kd" | "Ks" => {
+                let c = Color::new(
+                    float(tokens.next(), keyword)?,
+                    float(tokens.next(), keyword)?,
+                    float(tokens.next(), keyword)?,
+                );
+                if keyword == "Kd" {
+                    desc.kd = c;
+                } else {
+                    desc.ks = c;
+                }
+            }
+            "Ns" => desc.ns = float(tokens.next(), keyword)?,
+            "Ni" => desc.ni = float(tokens.next(), keyword)?,
+            "d" => desc.d = float(tokens.next(), keyword)?,
+            "Tr" => desc.d = 1.0 - float(tokens.next(), keyword)?,
+            "illum" => desc.illum = float(tokens.next(), keyword)? as u32,
+            "Pr" => desc.pr = Some(float(tokens.next(), keyword)?),
+            "Pm" => desc.pm = Some(float(tokens.next(), keyword)?),
+            "Ps" => desc.ps = Some(float(tokens.next(), keyword)?),
+            "Pc" => desc.pc = Some(float(tokens.next(), keyword)?),
+            "Pcr" => desc.pcr = Some(float(tokens.next(), keyword)?),
+            "map_Kd" | "map_Pr" | "map_Pm" => {
+                // Options such as -s or -o come first; the file name is the last token.
+                let file = tokens
+                    .last()
+                    .ok_or_else(|| error(format!("{} needs a file name", keyword)))?;
+                let texture_path = path.parent().unwrap_or(Path::new("")).join(file);
+                // Colors are stored in sRGB, scalar maps as plain data.
+                let texture = if keyword == "map_Kd" {
+                    ImageTexture::open(&texture_path)
+                } else {
+                    ImageTexture::open_linear(&texture_path)
+                }
+                .map_err(|e| error(format!("cannot load {}: {}", texture_path.display(), e)))?;
+                let texture: Arc<dyn Texture> = Arc::new(texture);
+                match keyword {
+                    "map_Kd" => desc.map_kd = Some(texture),
+                    "map_Pr" => desc.map_pr = Some(texture),
+                    _ => desc.map_pm = Some(texture),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((name, desc)) = current {
+        materials.insert(name, desc.to_material());
+    }
+    Ok(materials)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::HitRecord;
+    use crate::material::ScatterRecord;
+    use crate::ray::Ray;
+    use crate::rtweekend::seed_random;
+
+    fn parse(source: &str) -> Result<Vec<ObjGroup>, ObjError> {
+        parse_obj(source, Path::new("test.obj"))
+    }
+
+    #[test]
+    fn test_polygons_are_fan_triangulated() {
+        let groups = parse(
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0 2 0\n\
+             f 1 2 3 4 5\n",
+        )
+        .unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            groups[0].mesh.indices,
+            vec![[0, 1, 2], [0, 2, 3], [0, 3, 4]]
+        );
+    }
+
+    #[test]
+    fn test_groups_and_attributes() {
+        let groups = parse(
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+             vt 0 0\nvt 1 0\nvt 0 1\nvn 0 0 1\n\
+             g first\nf 1/1/1 2/2/1 3/3/1\n\
+             g second\nf -3//-1 -2//-1 -1//-1\n",
+        )
+        .unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].name, "first");
+        assert_eq!(groups[0].mesh.uvs.as_ref().unwrap()[1], (1.0, 0.0));
+        assert!(groups[0].mesh.normals.is_some());
+        assert_eq!(groups[1].name, "second");
+        assert!(groups[1].mesh.uvs.is_none());
+        assert_eq!(groups[1].mesh.positions[2], Point3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_errors_report_line_numbers() {
+        let err = parse("v 0 0 0\nv 1 0 0\n\nf 1 2 7\n").err().unwrap();
+        assert_eq!(err.line, 4);
+        assert_eq!(
+            err.to_string(),
+            "test.obj:4: vertex index 7 out of range (have 2)"
+        );
+
+        let err = parse("v 0 zero 0\n").err().unwrap();
+        assert_eq!(err.line, 1);
+
+        let err = parse("v 0 0 0\nusemtl missing\n").err().unwrap();
+        assert_eq!(err.line, 2);
+    }
+
+    // What the material does with light from a handful of directions: its scattered ray
+    // and attenuation, with the random numbers repeated, and its BSDF toward a handful of
+    // others. Materials compare equal by these.
+    fn responses(material: Arc<dyn Material>) -> Vec<Vec3> {
+        let rec = HitRecord {
+            p: Point3::new(0.0, 0.0, 0.0),
+            normal: Vec3::new(0.0, 1.0, 0.0),
+            tangent: Vec3::new(1.0, 0.0, 0.0),
+            bitangent: Vec3::new(0.0, 0.0, 1.0),
+            t: 1.0,
+            u: 0.5,
+            v: 0.5,
+            mat_ptr: material.clone(),
+            front_face: true,
+        };
+        let incoming = [Vec3::new(0.3, -1.0, 0.1), Vec3::new(-0.9, -0.2, 0.4)];
+        let outgoing = [
+            Vec3::new(0.3, 1.0, 0.1),
+            Vec3::new(-0.5, 0.6, 0.2),
+            Vec3::new(0.9, 0.1, -0.3),
+            Vec3::new(0.2, -0.8, 0.1),
+        ];
+        let mut responses = Vec::new();
+        for d_in in incoming {
+            let r_in = Ray::new(Point3::new(0.0, 1.0, 0.0), d_in, 0.0);
+            for seed in 0..4 {
+                seed_random(seed);
+                let mut srec = ScatterRecord::default();
+                assert!(material.scatter(&r_in, &rec, &mut srec));
+                responses.push(srec.attenuation);
+                responses.push(srec.specular_ray.direction);
+                if !srec.is_specular {
+                    for d_out in outgoing {
+                        let scattered = Ray::new(rec.p, d_out, 0.0);
+                        responses.push(material.eval(&r_in, &rec, &srec, &scattered));
+                    }
+                }
+            }
+        }
+        responses
+    }
+
+    // Whether two lists of responses agree to rounding.
+    fn same(a: &[Vec3], b: &[Vec3]) -> bool {
+        a.len() == b.len()
+            && a.iter()
+                .zip(b)
+                .all(|(a, b)| (*a - *b).length() <= 1e-9 * (1.0 + b.length()))
+    }
+
+    #[test]
+    fn test_mtl_parsing() {
+        let materials = parse_mtl(
+            "newmtl red\nKd 0.8 0.1 0.1\n\
+             newmtl glass\nNi 1.45\nd 0.2\n\
+             newmtl chrome\nKd 0 0 0\nKs 0.9 0.9 0.9\nNs 500\n\
+             newmtl brass\nKd 0.9 0.7 0.3\nPm 1\nPr 0.35\n",
+            Path::new("test.mtl"),
+        )
+        .unwrap();
+        assert_eq!(materials.len(), 4);
+
+        // The classic keys map onto the closest built-in material.
+        let parsed = |name: &str| responses(materials[name].clone());
+        let red = |r: f64| -> Arc<dyn Material> {
+            Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(r, 0.1, 0.1))))
+        };
+        assert!(same(&parsed("red"), &responses(red(0.8))));
+        assert!(!same(&parsed("red"), &responses(red(0.7))));
+        let glass = |ior: f64| -> Arc<dyn Material> { Arc::new(Dielectric::new(ior)) };
+        assert!(same(&parsed("glass"), &responses(glass(1.45))));
+        assert!(!same(&parsed("glass"), &responses(glass(1.5))));
+        // A Phong exponent of 500 is a fuzz of sqrt(2 / 502).
+        let chrome = |fuzz: f64| -> Arc<dyn Material> {
+            Arc::new(Metal::new(Color::new(0.9, 0.9, 0.9), fuzz))
+        };
+        assert!(same(
+            &parsed("chrome"),
+            &responses(chrome((2.0f64 / 502.0).sqrt()))
+        ));
+        assert!(!same(&parsed("chrome"), &responses(chrome(0.0))));
+
+        // The PBR keys select a principled material with exactly those parameters.
+        let materials = parse_mtl(
+            "newmtl brass\nKd 0.9 0.7 0.3\nPm 0.6\nPr 0.35\nPs 0.4\nPc 0.5\nPcr 0.1\n\
+             Ni 1.6\nd 0.9\n",
+            Path::new("test.mtl"),
+        )
+        .unwrap();
+        let constant = |value: f64| -> Arc<dyn Texture> {
+            Arc::new(SolidColor::from_rgb(value, value, value))
+        };
+        let brass = |roughness: f64| -> Arc<dyn Material> {
+            Arc::new(
+                Principled::new(Arc::new(SolidColor::from_rgb(0.9, 0.7, 0.3)))
+                    .with_metallic(constant(0.6))
+                    .with_roughness(constant(roughness))
+                    .with_specular(constant((0.6f64 / 2.6).powi(2) / 0.08))
+                    .with_sheen(constant(0.4))
+                    .with_clearcoat(constant(0.5), constant(0.1))
+                    .with_transmission(constant(0.1)),
+            )
+        };
+        let parsed = responses(materials["brass"].clone());
+        assert!(same(&parsed, &responses(brass(0.35))));
+        assert!(!same(&parsed, &responses(brass(0.5))));
+
+        let err = parse_mtl("newmtl a\nKd 1 1\n", Path::new("test.mtl"))
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "test.mtl:2: missing value for Kd");
+    }
+}
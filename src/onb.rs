@@ -0,0 +1,59 @@
+use crate::vec3::Vec3;
+
+// Orthonormal basis whose w axis is aligned with a given direction.
+pub struct Onb {
+    axis: [Vec3; 3],
+}
+
+impl Onb {
+    pub fn build_from_w(n: Vec3) -> Self {
+        let w = n.unit_vector();
+        let a = if w.x.abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v = w.cross(a).unit_vector();
+        let u = w.cross(v);
+        Self { axis: [u, v, w] }
+    }
+
+    // The basis around `n` whose u axis is `tangent` made perpendicular to it, falling
+    // back to an arbitrary one if the tangent runs along n.
+    pub fn build_from_w_and_u(n: Vec3, tangent: Vec3) -> Self {
+        let w = n.unit_vector();
+        let u = tangent - tangent.dot(w) * w;
+        if u.length_squared() < 1e-12 {
+            return Self::build_from_w(n);
+        }
+        let u = u.unit_vector();
+        Self {
+            axis: [u, w.cross(u), w],
+        }
+    }
+
+    pub fn u(&self) -> Vec3 {
+        self.axis[0]
+    }
+
+    pub fn v(&self) -> Vec3 {
+        self.axis[1]
+    }
+
+    pub fn w(&self) -> Vec3 {
+        self.axis[2]
+    }
+
+    pub fn local(&self, a: f64, b: f64, c: f64) -> Vec3 {
+        a * self.u() + b * self.v() + c * self.w()
+    }
+
+    pub fn local_vec(&self, a: Vec3) -> Vec3 {
+        self.local(a.x, a.y, a.z)
+    }
+
+    // The inverse of local_vec: world-space `a` expressed in this basis.
+    pub fn to_local(&self, a: Vec3) -> Vec3 {
+        Vec3::new(a.dot(self.u()), a.dot(self.v()), a.dot(self.w()))
+    }
+}
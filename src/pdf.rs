@@ -0,0 +1,165 @@
+use crate::background::Background;
+use crate::hittable::Hittable;
+use crate::onb::Onb;
+use crate::rtweekend::{PI, random_double};
+use crate::vec3::{Point3, Vec3};
+
+// A probability density over directions that can also be sampled.
+pub trait Pdf {
+    fn value(&self, direction: Vec3) -> f64;
+    fn generate(&self) -> Vec3;
+}
+
+pub struct CosinePdf {
+    uvw: Onb,
+}
+
+impl CosinePdf {
+    pub fn new(w: Vec3) -> Self {
+        Self {
+            uvw: Onb::build_from_w(w),
+        }
+    }
+}
+
+impl Pdf for CosinePdf {
+    fn value(&self, direction: Vec3) -> f64 {
+        let cosine = direction.unit_vector().dot(self.uvw.w());
+        if cosine <= 0.0 { 0.0 } else { cosine / PI }
+    }
+
+    fn generate(&self) -> Vec3 {
+        self.uvw.local_vec(Vec3::random_cosine_direction())
+    }
+}
+
+pub struct SpherePdf;
+
+impl Pdf for SpherePdf {
+    fn value(&self, _direction: Vec3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn generate(&self) -> Vec3 {
+        Vec3::random_unit_vector()
+    }
+}
+
+// Directions from `origin` toward the surface of `objects`, e.g. the lights of a scene.
+pub struct HittablePdf<'a> {
+    objects: &'a dyn Hittable,
+    origin: Point3,
+}
+
+impl<'a> HittablePdf<'a> {
+    pub fn new(objects: &'a dyn Hittable, origin: Point3) -> Self {
+        Self { objects, origin }
+    }
+}
+
+impl Pdf for HittablePdf<'_> {
+    fn value(&self, direction: Vec3) -> f64 {
+        self.objects.pdf_value(&self.origin, &direction)
+    }
+
+    fn generate(&self) -> Vec3 {
+        self.objects.random(&self.origin)
+    }
+}
+
+// Directions toward what a background samples, such as the sun or the bright parts of an
+// environment map. Only for backgrounds whose `random` returns a direction.
+pub struct BackgroundPdf<'a> {
+    background: &'a dyn Background,
+}
+
+impl<'a> BackgroundPdf<'a> {
+    pub fn new(background: &'a dyn Background) -> Self {
+        Self { background }
+    }
+}
+
+impl Pdf for BackgroundPdf<'_> {
+    fn value(&self, direction: Vec3) -> f64 {
+        self.background.pdf_value(&direction)
+    }
+
+    fn generate(&self) -> Vec3 {
+        self.background
+            .random()
+            .expect("BackgroundPdf needs a background that can be sampled")
+    }
+}
+
+// An equal-weight mix of two densities.
+pub struct MixturePdf<'a> {
+    p: [&'a dyn Pdf; 2],
+}
+
+impl<'a> MixturePdf<'a> {
+    pub fn new(p0: &'a dyn Pdf, p1: &'a dyn Pdf) -> Self {
+        Self { p: [p0, p1] }
+    }
+}
+
+impl Pdf for MixturePdf<'_> {
+    fn value(&self, direction: Vec3) -> f64 {
+        0.5 * self.p[0].value(direction) + 0.5 * self.p[1].value(direction)
+    }
+
+    fn generate(&self) -> Vec3 {
+        if random_double() < 0.5 {
+            self.p[0].generate()
+        } else {
+            self.p[1].generate()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarect::XzRect;
+    use crate::material::Lambertian;
+    use crate::texture::SolidColor;
+    use std::sync::Arc;
+
+    // Monte Carlo estimate of the integral of `pdf` over the unit sphere.
+    fn integrate(pdf: &dyn Pdf) -> f64 {
+        let n = 200_000;
+        let sum: f64 = (0..n).map(|_| pdf.value(Vec3::random_unit_vector())).sum();
+        sum * 4.0 * PI / n as f64
+    }
+
+    #[test]
+    fn test_pdfs_integrate_to_one() {
+        let cosine = CosinePdf::new(Vec3::new(0.3, 1.0, -0.2));
+        assert!((integrate(&cosine) - 1.0).abs() < 0.02);
+
+        let light = XzRect::new(
+            -1.0,
+            1.0,
+            -1.0,
+            1.0,
+            1.0,
+            Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+                1.0, 1.0, 1.0,
+            )))),
+        );
+        let to_light = HittablePdf::new(&light, Point3::new(0.0, 0.0, 0.0));
+        assert!((integrate(&to_light) - 1.0).abs() < 0.05);
+
+        let mixture = MixturePdf::new(&cosine, &to_light);
+        assert!((integrate(&mixture) - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_generated_directions_have_density() {
+        let cosine = CosinePdf::new(Vec3::new(0.0, 0.0, 1.0));
+        for _ in 0..1000 {
+            let d = cosine.generate();
+            assert!((d.length() - 1.0).abs() < 1e-9);
+            assert!(cosine.value(d) >= 0.0);
+        }
+    }
+}
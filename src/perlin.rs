@@ -0,0 +1,138 @@
+use crate::vec3::{Point3, Vec3};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+const POINT_COUNT: usize = 256;
+
+// Gradient noise on a permuted lattice. The same seed always gives the same field.
+pub struct Perlin {
+    ranvec: Vec<Vec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let ranvec = (0..POINT_COUNT)
+            .map(|_| {
+                Vec3::new(
+                    rng.random_range(-1.0..1.0),
+                    rng.random_range(-1.0..1.0),
+                    rng.random_range(-1.0..1.0),
+                )
+                .unit_vector()
+            })
+            .collect();
+
+        let perm_x = perlin_generate_perm(&mut rng);
+        let perm_y = perlin_generate_perm(&mut rng);
+        let perm_z = perlin_generate_perm(&mut rng);
+
+        Self {
+            ranvec,
+            perm_x,
+            perm_y,
+            perm_z,
+        }
+    }
+
+    // Returns a value in [-1, 1] that is zero at every lattice point.
+    pub fn noise(&self, p: &Point3) -> f64 {
+        let u = p.x - p.x.floor();
+        let v = p.y - p.y.floor();
+        let w = p.z - p.z.floor();
+
+        let i = p.x.floor() as i64;
+        let j = p.y.floor() as i64;
+        let k = p.z.floor() as i64;
+
+        let mut c = [[[Vec3::default(); 2]; 2]; 2];
+        for (di, plane) in c.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    let index = self.perm_x[((i + di as i64) & 255) as usize]
+                        ^ self.perm_y[((j + dj as i64) & 255) as usize]
+                        ^ self.perm_z[((k + dk as i64) & 255) as usize];
+                    *corner = self.ranvec[index];
+                }
+            }
+        }
+
+        perlin_interp(&c, u, v, w)
+    }
+
+    // Sum of `depth` octaves of noise with halving weights.
+    pub fn turb(&self, p: &Point3, depth: u32) -> f64 {
+        let mut accum = 0.0;
+        let mut temp_p = *p;
+        let mut weight = 1.0;
+
+        for _ in 0..depth {
+            accum += weight * self.noise(&temp_p);
+            weight *= 0.5;
+            temp_p *= 2.0;
+        }
+
+        accum.abs()
+    }
+}
+
+fn perlin_generate_perm(rng: &mut StdRng) -> Vec<usize> {
+    let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+    p.shuffle(rng);
+    p
+}
+
+fn perlin_interp(c: &[[[Vec3; 2]; 2]; 2], u: f64, v: f64, w: f64) -> f64 {
+    // Hermite smoothing hides the lattice.
+    let uu = u * u * (3.0 - 2.0 * u);
+    let vv = v * v * (3.0 - 2.0 * v);
+    let ww = w * w * (3.0 - 2.0 * w);
+    let mut accum = 0.0;
+
+    for (i, plane) in c.iter().enumerate() {
+        for (j, row) in plane.iter().enumerate() {
+            for (k, corner) in row.iter().enumerate() {
+                let (fi, fj, fk) = (i as f64, j as f64, k as f64);
+                let weight_v = Vec3::new(u - fi, v - fj, w - fk);
+                accum += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                    * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                    * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                    * corner.dot(weight_v);
+            }
+        }
+    }
+
+    accum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_gives_same_noise() {
+        let a = Perlin::new(42);
+        let b = Perlin::new(42);
+        let c = Perlin::new(7);
+        let p = Point3::new(1.3, -2.7, 0.45);
+        assert_eq!(a.noise(&p), b.noise(&p));
+        assert_eq!(a.turb(&p, 7), b.turb(&p, 7));
+        assert_ne!(a.noise(&p), c.noise(&p));
+    }
+
+    #[test]
+    fn test_noise_range_and_lattice_zeros() {
+        let perlin = Perlin::new(0);
+        assert_eq!(perlin.noise(&Point3::new(3.0, -1.0, 7.0)), 0.0);
+        for i in 0..1000 {
+            let t = i as f64 * 0.137;
+            let n = perlin.noise(&Point3::new(t, 0.5 * t, -0.3 * t));
+            assert!((-1.0..=1.0).contains(&n));
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use crate::hittable::HitRecord;
+use crate::material::{Material, ScatterRecord, surface_frame};
+use crate::microfacet::{TrowbridgeReitz, fresnel_dielectric, generalized_half_vector, refract};
+use crate::onb::Onb;
+use crate::pdf::Pdf;
+use crate::ray::Ray;
+use crate::rtweekend::{PI, random_double};
+use crate::texture::{SolidColor, Texture};
+use crate::vec3::{Color, Vec3};
+use std::sync::Arc;
+
+// One material covering plastics, metals, varnished and translucent surfaces, loosely
+// following Burley's Disney BSDF. Every parameter is a texture; scalar parameters read
+// the first channel and are clamped to [0, 1].
+pub struct Principled {
+    base_color: Arc<dyn Texture>,
+    metallic: Arc<dyn Texture>,
+    roughness: Arc<dyn Texture>,
+    specular: Arc<dyn Texture>, // 0.5 is a 4% reflectance, i.e. an index of refraction of 1.5
+    clearcoat: Arc<dyn Texture>,
+    clearcoat_roughness: Arc<dyn Texture>,
+    sheen: Arc<dyn Texture>,
+    transmission: Arc<dyn Texture>,
+}
+
+impl Principled {
+    pub fn new(base_color: Arc<dyn Texture>) -> Self {
+        Self {
+            base_color,
+            metallic: constant(0.0),
+            roughness: constant(0.5),
+            specular: constant(0.5),
+            clearcoat: constant(0.0),
+            clearcoat_roughness: constant(0.03),
+            sheen: constant(0.0),
+            transmission: constant(0.0),
+        }
+    }
+
+    pub fn with_metallic(mut self, metallic: Arc<dyn Texture>) -> Self {
+        self.metallic = metallic;
+        self
+    }
+
+    pub fn with_roughness(mut self, roughness: Arc<dyn Texture>) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    pub fn with_specular(mut self, specular: Arc<dyn Texture>) -> Self {
+        self.specular = specular;
+        self
+    }
+
+    pub fn with_clearcoat(
+        mut self,
+        clearcoat: Arc<dyn Texture>,
+        clearcoat_roughness: Arc<dyn Texture>,
+    ) -> Self {
+        self.clearcoat = clearcoat;
+        self.clearcoat_roughness = clearcoat_roughness;
+        self
+    }
+
+    pub fn with_sheen(mut self, sheen: Arc<dyn Texture>) -> Self {
+        self.sheen = sheen;
+        self
+    }
+
+    pub fn with_transmission(mut self, transmission: Arc<dyn Texture>) -> Self {
+        self.transmission = transmission;
+        self
+    }
+
+    fn lobes(&self, rec: &HitRecord) -> Lobes {
+        let scalar =
+            |texture: &Arc<dyn Texture>| texture.value(rec.u, rec.v, &rec.p).x.clamp(0.0, 1.0);
+
+        let metallic = scalar(&self.metallic);
+        let roughness = scalar(&self.roughness);
+        let transmission = scalar(&self.transmission);
+        let clearcoat = scalar(&self.clearcoat);
+        let clearcoat_roughness = scalar(&self.clearcoat_roughness);
+
+        // Specular maps to the normal-incidence reflectance 0.08 * specular.
+        let f0 = (0.08 * scalar(&self.specular)).clamp(1e-4, 0.99).sqrt();
+        let ior = (1.0 + f0) / (1.0 - f0);
+
+        let diffuse = (1.0 - metallic) * (1.0 - transmission);
+        let weights = [
+            diffuse,
+            0.25 * diffuse + metallic,
+            0.25 * clearcoat,
+            (1.0 - metallic) * transmission,
+        ];
+        let total: f64 = weights.iter().sum();
+
+        Lobes {
+            base_color: self.base_color.value(rec.u, rec.v, &rec.p),
+            metallic,
+            roughness,
+            sheen: scalar(&self.sheen),
+            clearcoat,
+            transmission,
+            // Only a transmissive surface has an inside to be hit from.
+            eta: if rec.front_face || transmission == 0.0 {
+                ior
+            } else {
+                1.0 / ior
+            },
+            specular: TrowbridgeReitz::from_roughness(roughness, roughness),
+            coat: TrowbridgeReitz::from_roughness(clearcoat_roughness, clearcoat_roughness),
+            weights: weights.map(|w| w / total),
+        }
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        let uvw = surface_frame(rec);
+        srec.is_specular = false;
+        srec.attenuation = Color::new(1.0, 1.0, 1.0);
+        srec.pdf_ptr = Some(Arc::new(PrincipledPdf {
+            wo: uvw.to_local(-r_in.direction.unit_vector()),
+            uvw,
+            lobes: self.lobes(rec),
+        }));
+        true
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, _srec: &ScatterRecord, scattered: &Ray) -> Color {
+        let uvw = surface_frame(rec);
+        let wo = uvw.to_local(-r_in.direction.unit_vector());
+        let wi = uvw.to_local(scattered.direction.unit_vector());
+        self.lobes(rec).eval(wo, wi)
+    }
+}
+
+fn constant(value: f64) -> Arc<dyn Texture> {
+    Arc::new(SolidColor::from_rgb(value, value, value))
+}
+
+fn schlick_weight(cosine: f64) -> f64 {
+    (1.0 - cosine.clamp(0.0, 1.0)).powi(5)
+}
+
+// The parameters at one shading point. Directions are local, with the normal along +z
+// and `wo` above the surface.
+#[derive(Clone, Copy)]
+struct Lobes {
+    base_color: Color,
+    metallic: f64,
+    roughness: f64,
+    sheen: f64,
+    clearcoat: f64,
+    transmission: f64,
+    eta: f64,
+    specular: TrowbridgeReitz,
+    coat: TrowbridgeReitz,
+    // Probabilities of sampling the diffuse, specular, clearcoat and transmission lobes.
+    weights: [f64; 4],
+}
+
+impl Lobes {
+    // The BSDF times the cosine toward `wi`.
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Color {
+        if wo.z <= 0.0 || wi.z == 0.0 {
+            return Color::default();
+        }
+        let white = Color::new(1.0, 1.0, 1.0);
+
+        if wi.z < 0.0 {
+            let weight = (1.0 - self.metallic) * self.transmission;
+            let Some(wm) = generalized_half_vector(wo, wi, self.eta) else {
+                return Color::default();
+            };
+            if weight == 0.0 {
+                return Color::default();
+            }
+            let f = fresnel_dielectric(wo.dot(wm), self.eta);
+            let denom = wi.dot(wm) + wo.dot(wm) / self.eta;
+            let bsdf = self.specular.d(wm)
+                * (1.0 - f)
+                * self.specular.g(wo, wi)
+                * (wi.dot(wm) * wo.dot(wm) / (wi.z * wo.z * denom * denom)).abs();
+            return weight * self.base_color * bsdf * wi.z.abs();
+        }
+
+        let wh = (wo + wi).unit_vector();
+        let cos_d = wi.dot(wh);
+
+        let dielectric_f = fresnel_dielectric(wo.dot(wh), self.eta);
+
+        // Burley's diffuse with retro-reflection at grazing angles, plus sheen. The base
+        // only receives the light the specular layer lets through.
+        let fd90 = 0.5 + 2.0 * self.roughness * cos_d * cos_d;
+        let fd = (1.0 + (fd90 - 1.0) * schlick_weight(wi.z))
+            * (1.0 + (fd90 - 1.0) * schlick_weight(wo.z));
+        let diffuse = (1.0 - self.metallic)
+            * (1.0 - self.transmission)
+            * ((1.0 - dielectric_f) * self.base_color * fd / PI
+                + self.sheen * schlick_weight(cos_d) * white);
+
+        // Dielectric Fresnel blended toward the tinted Schlick reflectance of a metal.
+        let metal_f = self.base_color + (white - self.base_color) * schlick_weight(wo.dot(wh));
+        let f = (1.0 - self.metallic) * dielectric_f * white + self.metallic * metal_f;
+        let specular = f * self.specular.d(wh) * self.specular.g(wo, wi) / (4.0 * wo.z * wi.z);
+
+        let coat_f = 0.04 + 0.96 * schlick_weight(wo.dot(wh));
+        let clearcoat = 0.25 * self.clearcoat * coat_f * self.coat.d(wh) * self.coat.g(wo, wi)
+            / (4.0 * wo.z * wi.z);
+
+        (diffuse + specular + clearcoat * white) * wi.z
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f64 {
+        if wo.z <= 0.0 || wi.z == 0.0 {
+            return 0.0;
+        }
+        let [diffuse, specular, clearcoat, transmission] = self.weights;
+
+        if wi.z < 0.0 {
+            let Some(wm) = generalized_half_vector(wo, wi, self.eta) else {
+                return 0.0;
+            };
+            let f = fresnel_dielectric(wo.dot(wm), self.eta);
+            let denom = wi.dot(wm) + wo.dot(wm) / self.eta;
+            return transmission * self.specular.d_visible(wo, wm) * wi.dot(wm).abs()
+                / (denom * denom)
+                * (1.0 - f);
+        }
+
+        let wh = (wo + wi).unit_vector();
+        let reflection = |distribution: &TrowbridgeReitz| {
+            distribution.d_visible(wo, wh) / (4.0 * wo.dot(wh).abs())
+        };
+        // The transmission lobe reflects with the Fresnel probability.
+        let f = fresnel_dielectric(wo.dot(wh), self.eta);
+        diffuse * wi.z / PI
+            + (specular + transmission * f) * reflection(&self.specular)
+            + clearcoat * reflection(&self.coat)
+    }
+
+    fn sample(&self, wo: Vec3) -> Vec3 {
+        let [diffuse, specular, clearcoat, _] = self.weights;
+        let xi = random_double();
+        if xi < diffuse {
+            return Vec3::random_cosine_direction();
+        }
+        if xi < diffuse + specular {
+            let wm = self
+                .specular
+                .sample_wm(wo, random_double(), random_double());
+            return Vec3::reflect(&-wo, &wm);
+        }
+        if xi < diffuse + specular + clearcoat {
+            let wm = self.coat.sample_wm(wo, random_double(), random_double());
+            return Vec3::reflect(&-wo, &wm);
+        }
+
+        let wm = self
+            .specular
+            .sample_wm(wo, random_double(), random_double());
+        let reflect_prob = fresnel_dielectric(wo.dot(wm), self.eta);
+        match refract(wo, wm, self.eta) {
+            Some(refracted) if random_double() >= reflect_prob => refracted,
+            _ => Vec3::reflect(&-wo, &wm),
+        }
+    }
+}
+
+struct PrincipledPdf {
+    uvw: Onb,
+    wo: Vec3,
+    lobes: Lobes,
+}
+
+impl Pdf for PrincipledPdf {
+    fn value(&self, direction: Vec3) -> f64 {
+        self.lobes
+            .pdf(self.wo, self.uvw.to_local(direction.unit_vector()))
+    }
+
+    fn generate(&self) -> Vec3 {
+        self.uvw.local_vec(self.lobes.sample(self.wo))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::tests::albedo_two_ways;
+
+    #[test]
+    fn test_sampling_matches_eval() {
+        let material = Principled::new(constant(0.8))
+            .with_metallic(constant(0.3))
+            .with_clearcoat(constant(1.0), constant(0.2))
+            .with_sheen(constant(0.5))
+            .with_transmission(constant(0.5));
+        albedo_two_ways(Arc::new(material), true);
+    }
+
+    #[test]
+    fn test_white_furnace() {
+        let cases = [
+            Principled::new(constant(1.0)),
+            Principled::new(constant(1.0)).with_metallic(constant(1.0)),
+            Principled::new(constant(1.0)).with_transmission(constant(1.0)),
+        ];
+        for material in cases {
+            let material: Arc<dyn Material> = Arc::new(material);
+            for front_face in [true, false] {
+                let albedo = albedo_two_ways(material.clone(), front_face);
+                assert!(albedo > 0.8 && albedo < 1.01, "albedo {}", albedo);
+            }
+        }
+    }
+}
@@ -1,13 +1,23 @@
+use crate::nested::MediumStack;
 use crate::vec3::{Point3, Vec3};
 
 pub struct Ray {
     pub origin: Point3,
     pub direction: Vec3,
+    pub time: f64,
+    pub wavelength: Option<f64>, // nm, set only in spectral rendering
+    pub media: MediumStack,      // the nested dielectrics the ray travels inside
 }
 
 impl Ray {
-    pub fn new(origin: Point3, direction: Vec3) -> Self {
-        Self { origin, direction }
+    pub fn new(origin: Point3, direction: Vec3, time: f64) -> Self {
+        Self {
+            origin,
+            direction,
+            time,
+            wavelength: None,
+            media: MediumStack::default(),
+        }
     }
 
     pub fn at(&self, t: f64) -> Point3 {
@@ -0,0 +1,108 @@
+use crate::integrator::Integrator;
+use crate::rtweekend::random_double;
+use crate::scenes::Scene;
+use crate::vec3::Color;
+use indicatif::{ParallelProgressIterator, ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+
+pub fn render(
+    scene: &Scene,
+    integrator: &dyn Integrator,
+    image_width: u32,
+    image_height: u32,
+    samples_per_pixel: u32,
+) -> image::RgbImage {
+    let mut imgbuf = image::ImageBuffer::new(image_width, image_height);
+
+    let pb = ProgressBar::new((image_width * image_height) as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template(
+                "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})",
+            )
+            .unwrap()
+            .progress_chars("#>-"),
+    );
+
+    let pixels: Vec<(u32, u32, &mut image::Rgb<u8>)> = imgbuf.enumerate_pixels_mut().collect();
+
+    pixels
+        .into_par_iter()
+        .progress_with(pb)
+        .for_each(|(i, j, pixel)| {
+            let mut pixel_color = Color::default();
+
+            for _ in 0..samples_per_pixel {
+                let u: f64 = (i as f64 + random_double()) / (image_width - 1) as f64;
+                let v: f64 =
+                    ((image_height - j - 1) as f64 + random_double()) / (image_height - 1) as f64;
+
+                let r = scene.cam.get_ray(u, v);
+                pixel_color += integrator.ray_color(r, scene);
+            }
+            pixel_color /= samples_per_pixel as f64;
+            *pixel = write_color(pixel_color);
+        });
+
+    imgbuf
+}
+
+pub fn write_color(color: Color) -> image::Rgb<u8> {
+    let r = (256.0 * color.x.sqrt().clamp(0.0, 0.9999)) as u8;
+    let g = (256.0 * color.y.sqrt().clamp(0.0, 0.9999)) as u8;
+    let b = (256.0 * color.z.sqrt().clamp(0.0, 0.9999)) as u8;
+
+    image::Rgb([r, g, b])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::background::SolidBackground;
+    use crate::camera::Camera;
+    use crate::hittable::Sphere;
+    use crate::hittable_list::HittableList;
+    use crate::integrator::PathTracer;
+    use crate::material::Lambertian;
+    use crate::texture::SolidColor;
+    use crate::vec3::{Point3, Vec3};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_write_color_applies_gamma_and_clamps() {
+        assert_eq!(
+            write_color(Color::new(0.0, 0.25, 1.0)),
+            image::Rgb([0, 128, 255])
+        );
+        assert_eq!(
+            write_color(Color::new(-1.0, 4.0, 0.0)),
+            image::Rgb([0, 255, 0])
+        );
+    }
+
+    #[test]
+    fn test_render_fills_every_pixel() {
+        let cam = Camera::new(
+            Point3::default(),
+            Point3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            1.5,
+            0.0,
+            1.0,
+        );
+        let background = Arc::new(SolidBackground::new(Color::new(0.25, 0.25, 0.25)));
+        // Something for the BVH to hold, out of sight behind the camera.
+        let behind = Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, -5.0),
+            1.0,
+            Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+                0.5, 0.5, 0.5,
+            )))),
+        ));
+        let scene = Scene::new(HittableList::with_object(behind), background, cam);
+        let img = render(&scene, &PathTracer::new(4), 6, 4, 2);
+        assert_eq!(img.dimensions(), (6, 4));
+        assert!(img.pixels().all(|p| *p == image::Rgb([128, 128, 128])));
+    }
+}
@@ -1,19 +1,32 @@
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cell::RefCell;
 
 pub const PI: f64 = std::f64::consts::PI;
 
+thread_local! {
+    // Each thread draws from its own generator, seeded from the OS unless reseeded.
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_rng(&mut rand::rng()));
+}
+
 pub fn degrees_to_radians(degrees: f64) -> f64 {
     degrees * PI / 180.0
 }
 
 pub fn random_double() -> f64 {
     // Returns a random real in [0,1).
-    rand::rng().random_range(0.0..1.0)
+    RNG.with(|rng| rng.borrow_mut().random_range(0.0..1.0))
 }
 
 pub fn random_double_range(min: f64, max: f64) -> f64 {
     // Returns a random real in [min,max).
-    rand::rng().random_range(min..max)
+    RNG.with(|rng| rng.borrow_mut().random_range(min..max))
+}
+
+// Restarts the calling thread's random numbers from `seed`, so that what it computes
+// next can be repeated exactly.
+pub fn seed_random(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
 }
 
 #[cfg(test)]
@@ -30,6 +43,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::manual_range_contains)]
     fn test_random_double() {
         for _ in 0..1000 {
             let r = random_double();
@@ -73,4 +87,13 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_seed_random_repeats() {
+        seed_random(42);
+        let first: Vec<f64> = (0..5).map(|_| random_double()).collect();
+        seed_random(42);
+        let second: Vec<f64> = (0..5).map(|_| random_double()).collect();
+        assert_eq!(first, second);
+    }
 }
@@ -0,0 +1,1111 @@
+use crate::aabox::AaBox;
+use crate::aarect::{XyRect, XzRect, YzRect};
+use crate::background::{Background, SkyGradient, SolidBackground};
+use crate::bump::{BumpMap, NormalMap};
+use crate::bvh::{BvhNode, BvhReport};
+use crate::camera::Camera;
+use crate::constant_medium::ConstantMedium;
+use crate::hittable::{HitRecord, Hittable, Sphere};
+use crate::hittable_list::HittableList;
+use crate::light::{DirectionalLight, Light, PointLight, SpotLight};
+use crate::mask::AlphaMask;
+use crate::mat4::Mat4;
+use crate::material::{
+    Coated, Conductor, Dielectric, DiffuseLight, Lambertian, Material, Metal, OrenNayar,
+    RoughDielectric, Subsurface, Velvet,
+};
+use crate::moving_sphere::MovingSphere;
+use crate::principled::Principled;
+use crate::ray::Ray;
+use crate::rtweekend::{random_double, random_double_range};
+use crate::sky::PreethamSky;
+use crate::spectrum::Dispersion;
+use crate::texture::{
+    CheckerTexture, ImageTexture, MarbleTexture, NoiseTexture, SolidColor, Texture, WoodTexture,
+};
+use crate::thin_film::ThinFilm;
+use crate::transform::{RotateY, Transform, Translate};
+use crate::triangle::{MeshData, TriangleMesh};
+use crate::vec3::{Color, Point3, Vec3};
+use std::sync::Arc;
+
+pub struct Scene {
+    pub world: Arc<dyn Hittable>,
+    pub bvh_report: BvhReport, // quality of the BVH built for `world`
+    pub lights: HittableList,  // emitters worth sampling directly
+    pub delta_lights: Vec<Arc<dyn Light>>,
+    pub background: Arc<dyn Background>,
+    pub cam: Camera,
+}
+
+impl Scene {
+    // Puts the objects of `world` into a BVH spanning the shutter interval [0, 1].
+    pub fn new(world: HittableList, background: Arc<dyn Background>, cam: Camera) -> Self {
+        let mut objects = world.objects;
+        let bvh = BvhNode::sah(&mut objects, 0.0, 1.0);
+        Self {
+            bvh_report: bvh.report(),
+            world: Arc::new(bvh),
+            lights: HittableList::new(),
+            delta_lights: Vec::new(),
+            background,
+            cam,
+        }
+    }
+
+    pub fn with_lights(mut self, lights: HittableList) -> Self {
+        self.lights = lights;
+        self
+    }
+
+    pub fn with_delta_lights(mut self, delta_lights: Vec<Arc<dyn Light>>) -> Self {
+        self.delta_lights = delta_lights;
+        self
+    }
+
+    // The first hit along `r` in (t_min, t_max) that stops the ray, passing through
+    // surfaces that their material's opacity cuts away.
+    pub fn hit(&self, r: &Ray, mut t_min: f64, t_max: f64) -> Option<HitRecord> {
+        loop {
+            let rec = self.world.hit(r, t_min, t_max)?;
+            let opacity = rec.mat_ptr.opacity(rec.u, rec.v, &rec.p);
+            if opacity >= 1.0 || (opacity > 0.0 && random_double() < opacity) {
+                return Some(rec);
+            }
+            t_min = rec.t + 0.001;
+        }
+    }
+}
+
+pub fn by_name(name: &str, aspect_ratio: f64) -> Option<Scene> {
+    match name {
+        "random" => Some(random_scene(aspect_ratio)),
+        "cornell_box" => Some(cornell_box(aspect_ratio)),
+        "cornell_smoke" => Some(cornell_smoke(aspect_ratio)),
+        "earth" => Some(earth(aspect_ratio)),
+        "perlin_spheres" => Some(perlin_spheres(aspect_ratio)),
+        "small_light" => Some(small_light(aspect_ratio)),
+        "delta_lights" => Some(delta_lights(aspect_ratio)),
+        "microfacet" => Some(microfacet(aspect_ratio)),
+        "principled" => Some(principled(aspect_ratio)),
+        "prism" => Some(prism(aspect_ratio)),
+        "glass_of_water" => Some(glass_of_water(aspect_ratio)),
+        "coatings" => Some(coatings(aspect_ratio)),
+        "translucent" => Some(translucent(aspect_ratio)),
+        "bump_map" => Some(bump_map(aspect_ratio)),
+        "cutouts" => Some(cutouts(aspect_ratio)),
+        "fabrics" => Some(fabrics(aspect_ratio)),
+        _ => None,
+    }
+}
+
+pub fn random_scene(aspect_ratio: f64) -> Scene {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(Lambertian::new(Arc::new(CheckerTexture::new(
+        Arc::new(SolidColor::new(Color::new(0.2, 0.3, 0.1))),
+        Arc::new(SolidColor::new(Color::new(0.9, 0.9, 0.9))),
+    ))));
+
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        checker,
+    )));
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_mat = random_double();
+            let center = Point3::new(
+                a as f64 + 0.9 * random_double(),
+                0.2,
+                b as f64 + 0.9 * random_double(),
+            );
+
+            if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
+                let sphere_material: Arc<dyn Material>;
+                if choose_mat < 0.8 {
+                    // diffuse
+                    let albedo = Color::random() * Color::random();
+                    sphere_material = Arc::new(Lambertian::new(Arc::new(SolidColor::new(albedo))));
+                    let center2 = center + Vec3::new(0.0, random_double_range(0.0, 0.5), 0.0);
+                    world.add(Arc::new(MovingSphere::new(
+                        center,
+                        center2,
+                        0.0,
+                        1.0,
+                        0.2,
+                        sphere_material,
+                    )));
+                } else if choose_mat < 0.95 {
+                    // metal
+                    let albedo = Color::random_range(0.5, 1.0);
+                    let fuzz = random_double();
+                    sphere_material = Arc::new(Metal::new(albedo, fuzz));
+                    world.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
+                } else {
+                    // glass
+                    sphere_material = Arc::new(Dielectric::new(1.5));
+                    world.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
+                }
+            }
+        }
+    }
+
+    let material1 = Arc::new(Dielectric::new(1.5));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        material1,
+    )));
+    let material2 = Arc::new(Lambertian::new(Arc::new(SolidColor::new(Color::new(
+        0.4, 0.2, 0.1,
+    )))));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-4.0, 1.0, 0.0),
+        1.0,
+        material2,
+    )));
+    let material3 = Arc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(4.0, 1.0, 0.0),
+        1.0,
+        material3,
+    )));
+
+    let lookfrom = Point3::new(13.0, 2.0, 3.0);
+    let lookat = Point3::new(0.0, 0.0, 0.0);
+    let vup = Vec3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = 10.0;
+    let aperture = 0.1;
+
+    let cam = Camera::new(
+        lookfrom,
+        lookat,
+        vup,
+        20.0, // vfov
+        aspect_ratio,
+        aperture,
+        dist_to_focus,
+    )
+    .with_shutter(0.0, 1.0);
+
+    Scene::new(world, Arc::new(SkyGradient::default()), cam)
+}
+
+// The empty room of the standard Cornell box: red, green and white walls lit by a
+// square lamp in the ceiling, spanning [0, 555] on every axis.
+pub fn cornell_box_room() -> HittableList {
+    let mut world = HittableList::new();
+
+    let red = Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+        0.65, 0.05, 0.05,
+    ))));
+    let white = Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+        0.73, 0.73, 0.73,
+    ))));
+    let green = Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+        0.12, 0.45, 0.15,
+    ))));
+    let light = cornell_box_light_material();
+
+    world.add(Arc::new(YzRect::new(0.0, 555.0, 0.0, 555.0, 555.0, green)));
+    world.add(Arc::new(YzRect::new(0.0, 555.0, 0.0, 555.0, 0.0, red)));
+    world.add(Arc::new(XzRect::new(
+        213.0, 343.0, 227.0, 332.0, 554.0, light,
+    )));
+    world.add(Arc::new(XzRect::new(
+        0.0,
+        555.0,
+        0.0,
+        555.0,
+        0.0,
+        white.clone(),
+    )));
+    world.add(Arc::new(XzRect::new(
+        0.0,
+        555.0,
+        0.0,
+        555.0,
+        555.0,
+        white.clone(),
+    )));
+    world.add(Arc::new(XyRect::new(0.0, 555.0, 0.0, 555.0, 555.0, white)));
+
+    world
+}
+
+fn cornell_box_light_material() -> Arc<DiffuseLight> {
+    Arc::new(DiffuseLight::new(Arc::new(SolidColor::from_rgb(
+        15.0, 15.0, 15.0,
+    ))))
+}
+
+// The ceiling lamp of `cornell_box_room`, for sampling light directly.
+pub fn cornell_box_lights() -> HittableList {
+    HittableList::with_object(Arc::new(XzRect::new(
+        213.0,
+        343.0,
+        227.0,
+        332.0,
+        554.0,
+        cornell_box_light_material(),
+    )))
+}
+
+pub fn cornell_box_camera(aspect_ratio: f64) -> Camera {
+    let lookfrom = Point3::new(278.0, 278.0, -800.0);
+    let lookat = Point3::new(278.0, 278.0, 0.0);
+    let vup = Vec3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = 10.0;
+    let aperture = 0.0;
+
+    Camera::new(
+        lookfrom,
+        lookat,
+        vup,
+        40.0, // vfov
+        aspect_ratio,
+        aperture,
+        dist_to_focus,
+    )
+}
+
+pub fn cornell_box(aspect_ratio: f64) -> Scene {
+    let mut world = cornell_box_room();
+
+    let white = Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+        0.73, 0.73, 0.73,
+    ))));
+    let box1: Arc<dyn Hittable> = Arc::new(AaBox::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(165.0, 330.0, 165.0),
+        white.clone(),
+    ));
+    let box1 = Arc::new(RotateY::new(box1, 15.0));
+    world.add(Arc::new(Translate::new(box1, Vec3::new(265.0, 0.0, 295.0))));
+
+    let box2: Arc<dyn Hittable> = Arc::new(AaBox::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(165.0, 165.0, 165.0),
+        white,
+    ));
+    let box2 = Arc::new(RotateY::new(box2, -18.0));
+    world.add(Arc::new(Translate::new(box2, Vec3::new(130.0, 0.0, 65.0))));
+
+    Scene::new(
+        world,
+        Arc::new(SolidBackground::new(Color::default())),
+        cornell_box_camera(aspect_ratio),
+    )
+    .with_lights(cornell_box_lights())
+}
+
+pub fn cornell_smoke(aspect_ratio: f64) -> Scene {
+    let mut world = cornell_box_room();
+
+    let white = Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+        0.73, 0.73, 0.73,
+    ))));
+
+    let box1: Arc<dyn Hittable> = Arc::new(AaBox::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(165.0, 330.0, 165.0),
+        white.clone(),
+    ));
+    let box1 = Arc::new(RotateY::new(box1, 15.0));
+    let box1 = Arc::new(Translate::new(box1, Vec3::new(265.0, 0.0, 295.0)));
+
+    let box2: Arc<dyn Hittable> = Arc::new(AaBox::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(165.0, 165.0, 165.0),
+        white,
+    ));
+    let box2 = Arc::new(RotateY::new(box2, -18.0));
+    let box2 = Arc::new(Translate::new(box2, Vec3::new(130.0, 0.0, 65.0)));
+
+    world.add(Arc::new(ConstantMedium::new(
+        box1,
+        0.01,
+        Arc::new(SolidColor::from_rgb(0.0, 0.0, 0.0)),
+    )));
+    world.add(Arc::new(ConstantMedium::new(
+        box2,
+        0.01,
+        Arc::new(SolidColor::from_rgb(1.0, 1.0, 1.0)),
+    )));
+
+    Scene::new(
+        world,
+        Arc::new(SolidBackground::new(Color::default())),
+        cornell_box_camera(aspect_ratio),
+    )
+    .with_lights(cornell_box_lights())
+}
+
+pub fn earth(aspect_ratio: f64) -> Scene {
+    let earth_texture = ImageTexture::open("earthmap.jpg").expect("Failed to load earthmap.jpg");
+    let earth_surface = Arc::new(Lambertian::new(Arc::new(earth_texture)));
+    let globe = Arc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 2.0, earth_surface));
+
+    let lookfrom = Point3::new(13.0, 2.0, 3.0);
+    let lookat = Point3::new(0.0, 0.0, 0.0);
+    let vup = Vec3::new(0.0, 1.0, 0.0);
+
+    Scene::new(
+        HittableList::with_object(globe),
+        Arc::new(SkyGradient::default()),
+        Camera::new(lookfrom, lookat, vup, 20.0, aspect_ratio, 0.0, 10.0),
+    )
+}
+
+pub fn perlin_spheres(aspect_ratio: f64) -> Scene {
+    let mut world = HittableList::new();
+
+    let noise = Arc::new(Lambertian::new(Arc::new(NoiseTexture::new(4.0, 1))));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        noise,
+    )));
+
+    let marble = Arc::new(Lambertian::new(Arc::new(MarbleTexture::new(4.0, 2))));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 2.0, -2.5),
+        2.0,
+        marble,
+    )));
+
+    let wood = Arc::new(Lambertian::new(Arc::new(WoodTexture::new(
+        8.0,
+        3,
+        Color::new(0.76, 0.55, 0.33),
+        Color::new(0.45, 0.27, 0.12),
+    ))));
+    world.add(Arc::new(Sphere::new(Point3::new(0.0, 2.0, 2.5), 2.0, wood)));
+
+    let lookfrom = Point3::new(13.0, 2.0, 3.0);
+    let lookat = Point3::new(0.0, 0.0, 0.0);
+    let vup = Vec3::new(0.0, 1.0, 0.0);
+
+    Scene::new(
+        world,
+        Arc::new(SkyGradient::default()),
+        Camera::new(lookfrom, lookat, vup, 30.0, aspect_ratio, 0.0, 10.0),
+    )
+}
+
+// A gray floor and sphere lit only by a small, very bright lamp: the case where sampling
+// the light directly matters most.
+pub fn small_light(aspect_ratio: f64) -> Scene {
+    let mut world = HittableList::new();
+
+    let gray = Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+        0.5, 0.5, 0.5,
+    ))));
+    world.add(Arc::new(XzRect::new(
+        -10.0,
+        10.0,
+        -10.0,
+        10.0,
+        0.0,
+        gray.clone(),
+    )));
+    world.add(Arc::new(Sphere::new(Point3::new(0.0, 1.0, 2.0), 1.0, gray)));
+
+    let lamp: Arc<dyn Hittable> = Arc::new(XzRect::new(
+        -0.25,
+        0.25,
+        0.75,
+        1.25,
+        4.0,
+        Arc::new(DiffuseLight::new(Arc::new(SolidColor::from_rgb(
+            200.0, 200.0, 200.0,
+        )))),
+    ));
+    world.add(lamp.clone());
+
+    let lookfrom = Point3::new(0.0, 3.0, -6.0);
+    let lookat = Point3::new(0.0, 0.5, 1.0);
+    let vup = Vec3::new(0.0, 1.0, 0.0);
+
+    Scene::new(
+        world,
+        Arc::new(SolidBackground::new(Color::default())),
+        Camera::new(lookfrom, lookat, vup, 40.0, aspect_ratio, 0.0, 10.0),
+    )
+    .with_lights(HittableList::with_object(lamp))
+}
+
+// Boxes and spheres on a floor, lit only by lights without geometry: a spot light, a
+// dim point light and a low directional light.
+pub fn delta_lights(aspect_ratio: f64) -> Scene {
+    let mut world = HittableList::new();
+
+    let white = Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+        0.73, 0.73, 0.73,
+    ))));
+    world.add(Arc::new(XzRect::new(
+        -20.0,
+        20.0,
+        -20.0,
+        20.0,
+        0.0,
+        white.clone(),
+    )));
+    world.add(Arc::new(XyRect::new(
+        -20.0,
+        20.0,
+        0.0,
+        20.0,
+        6.0,
+        white.clone(),
+    )));
+
+    let cube: Arc<dyn Hittable> = Arc::new(AaBox::new(
+        Point3::new(-1.0, 0.0, -1.0),
+        Point3::new(1.0, 2.0, 1.0),
+        white,
+    ));
+    world.add(Arc::new(Translate::new(
+        Arc::new(RotateY::new(cube, 30.0)),
+        Vec3::new(-2.5, 0.0, 2.0),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.5, 1.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+            0.65, 0.05, 0.05,
+        )))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(3.0, 1.0, 1.5),
+        1.0,
+        Arc::new(Metal::new(Color::new(0.8, 0.85, 0.88), 0.1)),
+    )));
+
+    let delta_lights: Vec<Arc<dyn Light>> = vec![
+        Arc::new(SpotLight::new(
+            Point3::new(0.0, 8.0, -2.0),
+            Vec3::new(0.0, -1.0, 0.3),
+            Color::new(120.0, 110.0, 90.0),
+            30.0,
+            20.0,
+        )),
+        Arc::new(PointLight::new(
+            Point3::new(-4.0, 4.0, -4.0),
+            Color::new(8.0, 10.0, 14.0),
+        )),
+        Arc::new(DirectionalLight::new(
+            Vec3::new(1.0, -0.5, 0.5),
+            Color::new(0.3, 0.25, 0.2),
+        )),
+    ];
+
+    let lookfrom = Point3::new(0.0, 4.0, -12.0);
+    let lookat = Point3::new(0.0, 1.0, 0.0);
+    let vup = Vec3::new(0.0, 1.0, 0.0);
+
+    Scene::new(
+        world,
+        Arc::new(SolidBackground::new(Color::default())),
+        Camera::new(lookfrom, lookat, vup, 40.0, aspect_ratio, 0.0, 10.0),
+    )
+    .with_delta_lights(delta_lights)
+}
+
+// A row of rough metals and frosted glass under a physical sky.
+pub fn microfacet(aspect_ratio: f64) -> Scene {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(Lambertian::new(Arc::new(CheckerTexture::new(
+        Arc::new(SolidColor::new(Color::new(0.2, 0.2, 0.2))),
+        Arc::new(SolidColor::new(Color::new(0.8, 0.8, 0.8))),
+    ))));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        checker,
+    )));
+
+    let materials: Vec<Arc<dyn Material>> = vec![
+        Arc::new(Conductor::gold(0.3)),
+        Arc::new(Conductor::copper(0.5)),
+        Arc::new(Conductor::aluminium(0.2).with_anisotropic_roughness(0.1, 0.6)),
+        Arc::new(Conductor::silver(0.05)),
+        Arc::new(RoughDielectric::new(1.5, 0.3)),
+    ];
+    for (i, material) in materials.into_iter().enumerate() {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(2.2 * i as f64 - 4.4, 1.0, 0.0),
+            1.0,
+            material,
+        )));
+    }
+
+    let lookfrom = Point3::new(0.0, 3.0, -12.0);
+    let lookat = Point3::new(0.0, 1.0, 0.0);
+    let vup = Vec3::new(0.0, 1.0, 0.0);
+
+    Scene::new(
+        world,
+        Arc::new(PreethamSky::new(35.0, 150.0, 3.0)),
+        Camera::new(lookfrom, lookat, vup, 30.0, aspect_ratio, 0.0, 10.0),
+    )
+}
+
+// One principled material set up as plastic, car paint, brushed brass, velvet and
+// frosted glass.
+pub fn principled(aspect_ratio: f64) -> Scene {
+    let mut world = HittableList::new();
+    let value = |v: f64| -> Arc<dyn Texture> { Arc::new(SolidColor::from_rgb(v, v, v)) };
+
+    let checker = Arc::new(Lambertian::new(Arc::new(CheckerTexture::new(
+        Arc::new(SolidColor::new(Color::new(0.2, 0.2, 0.2))),
+        Arc::new(SolidColor::new(Color::new(0.8, 0.8, 0.8))),
+    ))));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        checker,
+    )));
+
+    let materials = vec![
+        Principled::new(Arc::new(SolidColor::from_rgb(0.1, 0.3, 0.8))).with_roughness(value(0.3)),
+        Principled::new(Arc::new(SolidColor::from_rgb(0.6, 0.02, 0.02)))
+            .with_clearcoat(value(1.0), value(0.05)),
+        Principled::new(Arc::new(SolidColor::from_rgb(0.9, 0.7, 0.3)))
+            .with_metallic(value(1.0))
+            .with_roughness(value(0.35)),
+        Principled::new(Arc::new(SolidColor::from_rgb(0.3, 0.05, 0.3)))
+            .with_roughness(value(1.0))
+            .with_sheen(value(1.0)),
+        Principled::new(Arc::new(SolidColor::from_rgb(0.9, 1.0, 0.95)))
+            .with_roughness(value(0.2))
+            .with_transmission(value(1.0)),
+    ];
+    for (i, material) in materials.into_iter().enumerate() {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(2.2 * i as f64 - 4.4, 1.0, 0.0),
+            1.0,
+            Arc::new(material),
+        )));
+    }
+
+    let lookfrom = Point3::new(0.0, 3.0, -12.0);
+    let lookat = Point3::new(0.0, 1.0, 0.0);
+    let vup = Vec3::new(0.0, 1.0, 0.0);
+
+    Scene::new(
+        world,
+        Arc::new(PreethamSky::new(35.0, 150.0, 3.0)),
+        Camera::new(lookfrom, lookat, vup, 30.0, aspect_ratio, 0.0, 10.0),
+    )
+}
+
+// A flint glass prism in front of a glowing checkerboard. Rendered with the spectral
+// integrator, every edge seen through the prism splits into a rainbow.
+pub fn prism(aspect_ratio: f64) -> Scene {
+    let mut world = HittableList::new();
+
+    let wall = Arc::new(DiffuseLight::new(Arc::new(CheckerTexture::new(
+        Arc::new(SolidColor::from_rgb(0.0, 0.0, 0.0)),
+        Arc::new(SolidColor::from_rgb(2.0, 2.0, 2.0)),
+    ))));
+    world.add(Arc::new(XyRect::new(-40.0, 40.0, -30.0, 30.0, 8.0, wall)));
+
+    // An equilateral cross-section extruded along z, then stood upright.
+    let h = 3.0f64.sqrt();
+    let glass = Arc::new(Dielectric::new(1.78).with_dispersion(Dispersion::sf11()));
+    let mesh = MeshData {
+        positions: vec![
+            Point3::new(-1.0, -h / 3.0, -1.5),
+            Point3::new(1.0, -h / 3.0, -1.5),
+            Point3::new(0.0, 2.0 * h / 3.0, -1.5),
+            Point3::new(-1.0, -h / 3.0, 1.5),
+            Point3::new(1.0, -h / 3.0, 1.5),
+            Point3::new(0.0, 2.0 * h / 3.0, 1.5),
+        ],
+        normals: None,
+        uvs: None,
+        indices: vec![
+            [0, 2, 1],
+            [3, 4, 5],
+            [0, 1, 4],
+            [0, 4, 3],
+            [1, 2, 5],
+            [1, 5, 4],
+            [2, 0, 3],
+            [2, 3, 5],
+        ],
+        mat_ptr: glass,
+    };
+    world.add(Arc::new(Transform::new(
+        Arc::new(TriangleMesh::new(mesh)),
+        Mat4::rotation_y(20.0) * Mat4::rotation_x(-90.0),
+    )));
+
+    let lookfrom = Point3::new(0.0, 0.0, -7.0);
+    let lookat = Point3::new(0.0, 0.0, 0.0);
+    let vup = Vec3::new(0.0, 1.0, 0.0);
+
+    Scene::new(
+        world,
+        Arc::new(SolidBackground::new(Color::default())),
+        Camera::new(lookfrom, lookat, vup, 30.0, aspect_ratio, 0.0, 10.0),
+    )
+}
+
+pub fn glass_of_water(aspect_ratio: f64) -> Scene {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(CheckerTexture::new(
+        Arc::new(SolidColor::from_rgb(0.2, 0.3, 0.1)),
+        Arc::new(SolidColor::from_rgb(0.9, 0.9, 0.9)),
+    ));
+    world.add(Arc::new(XzRect::new(
+        -50.0,
+        50.0,
+        -50.0,
+        50.0,
+        0.0,
+        Arc::new(Lambertian::new(checker)),
+    )));
+
+    // An open-topped glass as one closed mesh: outer walls, inner walls and the rim.
+    // The water overlaps the glass, which owns the overlap by its higher priority.
+    let glass = Arc::new(
+        Dielectric::new(1.5)
+            .with_absorption(Color::new(0.15, 0.05, 0.1))
+            .with_priority(2),
+    );
+    let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+    let ring = |scale: f64, y: f64| corners.map(|(x, z)| Point3::new(scale * x, y, scale * z));
+    let mesh = MeshData {
+        positions: [
+            ring(1.0, 0.0),
+            ring(1.0, 2.5),
+            ring(0.9, 0.15),
+            ring(0.9, 2.5),
+        ]
+        .concat(),
+        normals: None,
+        uvs: None,
+        indices: vec![
+            [0, 1, 2],
+            [0, 2, 3],
+            [11, 10, 9],
+            [11, 9, 8],
+            [4, 5, 1],
+            [4, 1, 0],
+            [8, 9, 13],
+            [8, 13, 12],
+            [12, 13, 5],
+            [12, 5, 4],
+            [5, 6, 2],
+            [5, 2, 1],
+            [9, 10, 14],
+            [9, 14, 13],
+            [13, 14, 6],
+            [13, 6, 5],
+            [6, 7, 3],
+            [6, 3, 2],
+            [10, 11, 15],
+            [10, 15, 14],
+            [14, 15, 7],
+            [14, 7, 6],
+            [7, 4, 0],
+            [7, 0, 3],
+            [11, 8, 12],
+            [11, 12, 15],
+            [15, 12, 4],
+            [15, 4, 7],
+        ],
+        mat_ptr: glass,
+    };
+    world.add(Arc::new(TriangleMesh::new(mesh)));
+    let water = Arc::new(
+        Dielectric::new(1.33)
+            .with_absorption(Color::new(0.6, 0.2, 0.05))
+            .with_priority(1),
+    );
+    world.add(Arc::new(AaBox::new(
+        Point3::new(-0.95, 0.1, -0.95),
+        Point3::new(0.95, 1.6, 0.95),
+        water,
+    )));
+
+    // A straw leaning in the glass shows the bend at the water surface.
+    let straw: Arc<dyn Hittable> = Arc::new(AaBox::new(
+        Point3::new(-0.06, 0.0, -0.06),
+        Point3::new(0.06, 3.2, 0.06),
+        Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+            0.8, 0.1, 0.1,
+        )))),
+    ));
+    world.add(Arc::new(Transform::new(
+        straw,
+        Mat4::translation(Vec3::new(-0.5, 0.2, 0.0)) * Mat4::rotation_z(-20.0),
+    )));
+
+    let lookfrom = Point3::new(1.5, 3.0, -6.0);
+    let lookat = Point3::new(0.0, 1.0, 0.0);
+    let vup = Vec3::new(0.0, 1.0, 0.0);
+
+    Scene::new(
+        world,
+        Arc::new(SkyGradient::default()),
+        Camera::new(lookfrom, lookat, vup, 30.0, aspect_ratio, 0.0, 10.0),
+    )
+}
+
+pub fn coatings(aspect_ratio: f64) -> Scene {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(CheckerTexture::new(
+        Arc::new(SolidColor::from_rgb(0.1, 0.1, 0.1)),
+        Arc::new(SolidColor::from_rgb(0.8, 0.8, 0.8)),
+    ));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::new(checker)),
+    )));
+
+    // Car paint: a clear coat over a diffuse and over a brushed metallic base.
+    let paint = Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+        0.6, 0.02, 0.02,
+    ))));
+    let flakes = Arc::new(Metal::new(Color::new(0.1, 0.2, 0.6), 0.4));
+    let materials: [Arc<dyn Material>; 4] = [
+        Arc::new(Coated::new(paint, 1.5)),
+        Arc::new(Coated::new(flakes, 1.5)),
+        // Steel tinted by an oxide layer.
+        Arc::new(
+            Metal::new(Color::new(0.6, 0.6, 0.6), 0.0).with_thin_film(ThinFilm::new(300.0, 2.2)),
+        ),
+        // A soap bubble.
+        Arc::new(Dielectric::new(1.0).with_thin_film(ThinFilm::new(500.0, 1.33))),
+    ];
+    for (i, material) in materials.into_iter().enumerate() {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(-3.3 + 2.2 * i as f64, 1.0, 0.0),
+            1.0,
+            material,
+        )));
+    }
+
+    let lookfrom = Point3::new(0.0, 3.0, -10.0);
+    let lookat = Point3::new(0.0, 0.8, 0.0);
+    let vup = Vec3::new(0.0, 1.0, 0.0);
+
+    Scene::new(
+        world,
+        Arc::new(SkyGradient::default()),
+        Camera::new(lookfrom, lookat, vup, 40.0, aspect_ratio, 0.0, 10.0),
+    )
+}
+
+// A jade sphere and a block of candle wax, lit from behind so light glows through them.
+pub fn translucent(aspect_ratio: f64) -> Scene {
+    let mut world = HittableList::new();
+
+    let gray = Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+        0.5, 0.5, 0.5,
+    ))));
+    world.add(Arc::new(XzRect::new(-20.0, 20.0, -20.0, 20.0, 0.0, gray)));
+
+    let jade = Arc::new(Subsurface::new(
+        Color::new(0.3, 0.8, 0.5),
+        Color::new(0.05, 0.2, 0.1),
+        1.6,
+    ));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-1.3, 1.0, 0.0),
+        1.0,
+        jade,
+    )));
+
+    let wax = Arc::new(Subsurface::new(
+        Color::new(0.95, 0.85, 0.6),
+        Color::new(0.25, 0.2, 0.15),
+        1.44,
+    ));
+    let block: Arc<dyn Hittable> = Arc::new(AaBox::new(
+        Point3::new(-0.7, 0.0, -0.7),
+        Point3::new(0.7, 2.4, 0.7),
+        wax,
+    ));
+    world.add(Arc::new(Translate::new(
+        Arc::new(RotateY::new(block, 30.0)),
+        Vec3::new(1.4, 0.0, 0.5),
+    )));
+
+    let lamp: Arc<dyn Hittable> = Arc::new(Sphere::new(
+        Point3::new(0.0, 4.0, 5.0),
+        1.0,
+        Arc::new(DiffuseLight::new(Arc::new(SolidColor::from_rgb(
+            15.0, 15.0, 15.0,
+        )))),
+    ));
+    world.add(lamp.clone());
+
+    let lookfrom = Point3::new(0.0, 2.5, -7.0);
+    let lookat = Point3::new(0.0, 1.0, 0.0);
+    let vup = Vec3::new(0.0, 1.0, 0.0);
+
+    Scene::new(
+        world,
+        Arc::new(SolidBackground::new(Color::new(0.05, 0.05, 0.07))),
+        Camera::new(lookfrom, lookat, vup, 35.0, aspect_ratio, 0.0, 10.0),
+    )
+    .with_lights(HittableList::with_object(lamp))
+}
+
+// Surface detail without geometry: bump-mapped spheres on a floor of tilted tiles.
+pub fn bump_map(aspect_ratio: f64) -> Scene {
+    let mut world = HittableList::new();
+
+    let gray = Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+        0.6, 0.6, 0.6,
+    ))));
+    let tiles = Arc::new(CheckerTexture::new(
+        Arc::new(SolidColor::from_rgb(0.9, 0.5, 0.8)),
+        Arc::new(SolidColor::from_rgb(0.5, 0.1, 0.8)),
+    ));
+    // Raised off y = 0, where the solid checker pattern vanishes.
+    world.add(Arc::new(XzRect::new(
+        -20.0,
+        20.0,
+        -20.0,
+        20.0,
+        0.15,
+        Arc::new(NormalMap::new(gray, tiles)),
+    )));
+
+    let orange = Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+        0.8, 0.4, 0.1,
+    ))));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-1.2, 1.15, 0.0),
+        1.0,
+        Arc::new(BumpMap::new(
+            orange,
+            Arc::new(NoiseTexture::new(6.0, 1)),
+            0.15,
+        )),
+    )));
+    let steel = Arc::new(Metal::new(Color::new(0.8, 0.8, 0.85), 0.0));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(1.2, 1.15, 0.0),
+        1.0,
+        Arc::new(BumpMap::new(
+            steel,
+            Arc::new(MarbleTexture::new(3.0, 2)),
+            0.03,
+        )),
+    )));
+
+    let lookfrom = Point3::new(0.0, 2.5, -7.0);
+    let lookat = Point3::new(0.0, 0.8, 0.0);
+    let vup = Vec3::new(0.0, 1.0, 0.0);
+
+    Scene::new(
+        world,
+        Arc::new(SkyGradient::default()),
+        Camera::new(lookfrom, lookat, vup, 35.0, aspect_ratio, 0.0, 10.0),
+    )
+}
+
+// Opaque inside a pointed leaf stretched along u, for cutting leaves out of quads.
+struct LeafShape;
+
+impl Texture for LeafShape {
+    fn value(&self, u: f64, v: f64, _p: &Point3) -> Color {
+        let half_width = 0.45 * (std::f64::consts::PI * u).sin().powf(0.7);
+        if (v - 0.5).abs() < half_width {
+            Color::new(1.0, 1.0, 1.0)
+        } else {
+            Color::default()
+        }
+    }
+}
+
+pub fn cutouts(aspect_ratio: f64) -> Scene {
+    let mut world = HittableList::new();
+
+    let ground = Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+        0.7, 0.65, 0.6,
+    ))));
+    world.add(Arc::new(XzRect::new(-20.0, 20.0, -20.0, 20.0, 0.0, ground)));
+
+    // A lattice fence whose holes come from thresholding a checker pattern.
+    let wood = Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+        0.45, 0.3, 0.15,
+    ))));
+    let holes = Arc::new(CheckerTexture::new(
+        Arc::new(SolidColor::from_rgb(1.0, 1.0, 1.0)),
+        Arc::new(SolidColor::from_rgb(0.0, 0.0, 0.0)),
+    ));
+    world.add(Arc::new(XyRect::new(
+        -4.0,
+        4.0,
+        0.0,
+        2.5,
+        2.0,
+        Arc::new(AlphaMask::new(wood, holes).with_threshold(0.5)),
+    )));
+
+    // A plant of leaf cards spiralling up a stem.
+    let leaf = Arc::new(AlphaMask::new(
+        Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+            0.15, 0.45, 0.1,
+        )))),
+        Arc::new(LeafShape),
+    ));
+    for i in 0..16 {
+        let card = Arc::new(XyRect::new(0.0, 1.0, -0.25, 0.25, 0.0, leaf.clone()));
+        let i = i as f64;
+        world.add(Arc::new(Transform::new(
+            card,
+            Mat4::translation(Vec3::new(0.0, 0.4 + 0.08 * i, 0.0))
+                * Mat4::rotation_y(137.5 * i)
+                * Mat4::rotation_z(25.0 - 2.0 * i)
+                * Mat4::rotation_x(-70.0),
+        )));
+    }
+    world.add(Arc::new(AaBox::new(
+        Point3::new(-0.03, 0.0, -0.03),
+        Point3::new(0.03, 1.7, 0.03),
+        leaf.base.clone(),
+    )));
+
+    // A sheet of gauze that lets light through at random where the noise is thin.
+    let gauze = Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+        0.9, 0.9, 0.95,
+    ))));
+    world.add(Arc::new(Transform::new(
+        Arc::new(XyRect::new(
+            -1.0,
+            1.0,
+            0.0,
+            2.0,
+            0.0,
+            Arc::new(AlphaMask::new(gauze, Arc::new(NoiseTexture::new(4.0, 3)))),
+        )),
+        Mat4::translation(Vec3::new(-2.2, 0.0, 0.3)) * Mat4::rotation_y(-35.0),
+    )));
+
+    let delta_lights: Vec<Arc<dyn Light>> = vec![Arc::new(DirectionalLight::new(
+        Vec3::new(0.4, -0.8, -0.6),
+        Color::new(2.5, 2.3, 2.0),
+    ))];
+
+    let lookfrom = Point3::new(0.5, 2.5, -6.0);
+    let lookat = Point3::new(0.0, 1.0, 0.5);
+    let vup = Vec3::new(0.0, 1.0, 0.0);
+
+    Scene::new(
+        world,
+        Arc::new(SkyGradient::default()),
+        Camera::new(lookfrom, lookat, vup, 45.0, aspect_ratio, 0.0, 10.0),
+    )
+    .with_delta_lights(delta_lights)
+}
+
+pub fn fabrics(aspect_ratio: f64) -> Scene {
+    let mut world = HittableList::new();
+
+    let floor = Arc::new(OrenNayar::new(
+        Arc::new(CheckerTexture::new(
+            Arc::new(SolidColor::from_rgb(0.3, 0.3, 0.3)),
+            Arc::new(SolidColor::from_rgb(0.7, 0.7, 0.7)),
+        )),
+        30.0,
+    ));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        floor,
+    )));
+
+    // Smooth diffuse, then clay and dust-rough diffuse, then velvets.
+    let clay = Arc::new(SolidColor::from_rgb(0.7, 0.4, 0.25));
+    let materials: Vec<Arc<dyn Material>> = vec![
+        Arc::new(Lambertian::new(clay.clone())),
+        Arc::new(OrenNayar::new(clay.clone(), 30.0)),
+        Arc::new(OrenNayar::new(clay, 90.0)),
+        Arc::new(Velvet::new(
+            Arc::new(SolidColor::from_rgb(0.35, 0.02, 0.05)),
+            Arc::new(SolidColor::from_rgb(0.9, 0.5, 0.6)),
+            0.3,
+        )),
+        Arc::new(Velvet::new(
+            Arc::new(SolidColor::from_rgb(0.02, 0.05, 0.2)),
+            Arc::new(MarbleTexture::new(4.0, 5)),
+            0.6,
+        )),
+    ];
+    for (i, material) in materials.into_iter().enumerate() {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(2.2 * i as f64 - 4.4, 1.0, 0.0),
+            1.0,
+            material,
+        )));
+    }
+
+    // Behind and above, so the velvets light up around their rims.
+    let lamp: Arc<dyn Hittable> = Arc::new(Sphere::new(
+        Point3::new(0.0, 6.0, 6.0),
+        1.5,
+        Arc::new(DiffuseLight::new(Arc::new(SolidColor::from_rgb(
+            12.0, 12.0, 12.0,
+        )))),
+    ));
+    world.add(lamp.clone());
+
+    let lookfrom = Point3::new(0.0, 3.0, -12.0);
+    let lookat = Point3::new(0.0, 1.0, 0.0);
+    let vup = Vec3::new(0.0, 1.0, 0.0);
+
+    Scene::new(
+        world,
+        Arc::new(SolidBackground::new(Color::new(0.15, 0.15, 0.18))),
+        Camera::new(lookfrom, lookat, vup, 28.0, aspect_ratio, 0.0, 10.0),
+    )
+    .with_lights(HittableList::with_object(lamp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_scene_builds() {
+        for name in [
+            "random",
+            "cornell_box",
+            "cornell_smoke",
+            "earth",
+            "perlin_spheres",
+            "small_light",
+            "delta_lights",
+            "microfacet",
+            "principled",
+            "prism",
+            "glass_of_water",
+            "coatings",
+            "translucent",
+            "bump_map",
+            "cutouts",
+            "fabrics",
+        ] {
+            // The earth needs its image, which isn't part of the repository.
+            if name == "earth" && !std::path::Path::new("earthmap.jpg").exists() {
+                continue;
+            }
+            assert!(by_name(name, 16.0 / 9.0).is_some(), "{}", name);
+        }
+        assert!(by_name("nowhere", 16.0 / 9.0).is_none());
+    }
+}
@@ -0,0 +1,221 @@
+use crate::background::Background;
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::rtweekend::{PI, degrees_to_radians, random_double};
+use crate::spectrum::xyz_to_linear_srgb;
+use crate::vec3::{Color, Vec3};
+
+// Angular radius of the sun seen from the earth.
+const SUN_ANGULAR_RADIUS: f64 = 0.00465;
+
+// Preetham, Shirley and Smits' analytic daylight model plus a sampleable sun disk.
+pub struct PreethamSky {
+    sun_direction: Vec3,
+    sun_radiance: Color,
+    cos_sun_radius: f64,
+    zenith: [f64; 3], // Y, x, y at the zenith
+    perez: [[f64; 5]; 3],
+    intensity: f64,
+}
+
+impl PreethamSky {
+    // `elevation` is the sun's angle above the horizon and `azimuth` is measured from +z
+    // toward +x, both in degrees. Turbidity ranges from 2 (very clear) to about 10 (hazy).
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64) -> Self {
+        let elevation_rad = degrees_to_radians(elevation);
+        let azimuth_rad = degrees_to_radians(azimuth);
+        let sun_direction = Vec3::new(
+            elevation_rad.cos() * azimuth_rad.sin(),
+            elevation_rad.sin(),
+            elevation_rad.cos() * azimuth_rad.cos(),
+        );
+
+        let t = turbidity;
+        let theta_s = PI / 2.0 - elevation_rad; // sun zenith angle
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let zenith_chromaticity = |m: [[f64; 4]; 3]| {
+            let thetas = [theta_s.powi(3), theta_s.powi(2), theta_s, 1.0];
+            let ts = [t * t, t, 1.0];
+            (0..3)
+                .map(|i| ts[i] * (0..4).map(|j| m[i][j] * thetas[j]).sum::<f64>())
+                .sum::<f64>()
+        };
+        let zenith_x = zenith_chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let zenith_y = zenith_chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        // Normalize each channel by the Perez function at the zenith.
+        let zenith = [zenith_luminance, zenith_x, zenith_y];
+        let zenith = std::array::from_fn(|i| zenith[i] / perez_f(&perez[i], 0.0, theta_s));
+
+        Self {
+            sun_direction,
+            sun_radiance: sun_radiance(theta_s, t),
+            cos_sun_radius: SUN_ANGULAR_RADIUS.cos(),
+            zenith,
+            perez,
+            intensity: 0.05,
+        }
+    }
+
+    // Scales the sky and the sun; the model's units are kcd/m^2.
+    pub fn with_intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn sun_direction(&self) -> Vec3 {
+        self.sun_direction
+    }
+
+    pub fn sky_radiance(&self, direction: &Vec3) -> Color {
+        let d = direction.unit_vector();
+        // The model is only defined above the horizon; hold the horizon color below it.
+        let cos_theta = d.y.max(0.01);
+        let theta = cos_theta.acos();
+        let gamma = d.dot(self.sun_direction).clamp(-1.0, 1.0).acos();
+
+        let luminance = self.zenith[0] * perez_f(&self.perez[0], theta, gamma);
+        let x = self.zenith[1] * perez_f(&self.perez[1], theta, gamma);
+        let y = self.zenith[2] * perez_f(&self.perez[2], theta, gamma);
+
+        self.intensity * xyy_to_linear_srgb(x, y, luminance)
+    }
+
+    fn sun_solid_angle(&self) -> f64 {
+        2.0 * PI * (1.0 - self.cos_sun_radius)
+    }
+}
+
+impl Background for PreethamSky {
+    fn value(&self, r: &Ray) -> Color {
+        let mut color = self.sky_radiance(&r.direction);
+        if r.direction.unit_vector().dot(self.sun_direction) >= self.cos_sun_radius {
+            color += self.intensity * self.sun_radiance;
+        }
+        color
+    }
+
+    fn random(&self) -> Option<Vec3> {
+        if self.sun_direction.y <= 0.0 {
+            return None;
+        }
+        // Uniform direction inside the cone of the sun disk.
+        let z = 1.0 - random_double() * (1.0 - self.cos_sun_radius);
+        let phi = 2.0 * PI * random_double();
+        let r = (1.0 - z * z).sqrt();
+        let uvw = Onb::build_from_w(self.sun_direction);
+        Some(uvw.local(r * phi.cos(), r * phi.sin(), z))
+    }
+
+    fn pdf_value(&self, direction: &Vec3) -> f64 {
+        if self.sun_direction.y > 0.0
+            && direction.unit_vector().dot(self.sun_direction) >= self.cos_sun_radius
+        {
+            1.0 / self.sun_solid_angle()
+        } else {
+            0.0
+        }
+    }
+}
+
+// Perez et al.'s sky luminance distribution.
+fn perez_f(c: &[f64; 5], theta: f64, gamma: f64) -> f64 {
+    (1.0 + c[0] * (c[1] / theta.cos()).exp())
+        * (1.0 + c[2] * (c[3] * gamma).exp() + c[4] * gamma.cos() * gamma.cos())
+}
+
+fn xyy_to_linear_srgb(x: f64, y: f64, luminance: f64) -> Color {
+    if y <= 0.0 {
+        return Color::default();
+    }
+    let xyz = Vec3::new(x * luminance / y, luminance, (1.0 - x - y) * luminance / y);
+    let rgb = xyz_to_linear_srgb(xyz);
+    Color::new(rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0))
+}
+
+// Radiance of the sun disk after Rayleigh and aerosol extinction along the air mass
+// (Preetham et al., appendix), for the red, green and blue wavelengths 680, 550, 440 nm.
+fn sun_radiance(theta_s: f64, turbidity: f64) -> Color {
+    if theta_s >= PI / 2.0 {
+        return Color::default();
+    }
+    let theta_deg = theta_s.to_degrees();
+    let air_mass = 1.0 / (theta_s.cos() + 0.15 * (93.885 - theta_deg).powf(-1.253));
+    let beta = 0.04608 * turbidity - 0.04586;
+
+    let transmittance = |lambda_um: f64| {
+        let rayleigh = (-0.008735 * lambda_um.powf(-4.08) * air_mass).exp();
+        let aerosol = (-beta * lambda_um.powf(-1.3) * air_mass).exp();
+        rayleigh * aerosol
+    };
+
+    // Unattenuated, the sun is about 1.6e6 kcd/m^2 bright.
+    1.6e6
+        * Color::new(
+            transmittance(0.68),
+            transmittance(0.55),
+            transmittance(0.44),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sky_is_brighter_toward_the_sun() {
+        let sky = PreethamSky::new(30.0, 90.0, 3.0);
+        let toward = sky.sky_radiance(&Vec3::new(1.0, 0.3, 0.0));
+        let away = sky.sky_radiance(&Vec3::new(-1.0, 0.3, 0.0));
+        assert!(toward.y > away.y);
+        // A clear sky is blue away from the sun.
+        assert!(away.z > away.x);
+    }
+
+    #[test]
+    fn test_sun_samples_lie_in_the_disk() {
+        let sky = PreethamSky::new(45.0, 0.0, 2.5);
+        for _ in 0..1000 {
+            let d = sky.random().unwrap();
+            assert!(sky.pdf_value(&d) > 0.0);
+            let r = Ray::new(Vec3::default(), d, 0.0);
+            assert!(sky.value(&r).y > sky.sky_radiance(&d).y);
+        }
+        // Once the sun has set there is nothing to sample.
+        assert!(PreethamSky::new(-5.0, 0.0, 2.5).random().is_none());
+    }
+}
@@ -0,0 +1,299 @@
+use crate::vec3::{Color, Vec3};
+use std::ops::{Add, AddAssign, Mul, MulAssign};
+use std::sync::OnceLock;
+
+pub const LAMBDA_MIN: f64 = 360.0;
+pub const LAMBDA_MAX: f64 = 830.0;
+pub const N_SAMPLES: usize = 4;
+
+// The wavelengths (in nm) one camera path carries. The first is the hero wavelength;
+// the others are evenly rotated from it across the visible range.
+#[derive(Clone, Copy)]
+pub struct SampledWavelengths {
+    pub lambda: [f64; N_SAMPLES],
+    pub pdf: [f64; N_SAMPLES],
+}
+
+impl SampledWavelengths {
+    pub fn sample_uniform(u: f64) -> Self {
+        let range = LAMBDA_MAX - LAMBDA_MIN;
+        let hero = LAMBDA_MIN + u * range;
+        let lambda = std::array::from_fn(|i| {
+            let l = hero + i as f64 * range / N_SAMPLES as f64;
+            if l > LAMBDA_MAX { l - range } else { l }
+        });
+        Self {
+            lambda,
+            pdf: [1.0 / range; N_SAMPLES],
+        }
+    }
+
+    pub fn hero(&self) -> f64 {
+        self.lambda[0]
+    }
+
+    // Once a path depends on the wavelength, e.g. after refraction through dispersive
+    // glass, only the hero wavelength remains valid.
+    pub fn terminate_secondary(&mut self) {
+        if self.secondary_terminated() {
+            return;
+        }
+        self.pdf[0] /= N_SAMPLES as f64;
+        for pdf in &mut self.pdf[1..] {
+            *pdf = 0.0;
+        }
+    }
+
+    pub fn secondary_terminated(&self) -> bool {
+        self.pdf[1..].iter().all(|&pdf| pdf == 0.0)
+    }
+}
+
+// Values of a spectral distribution at the sampled wavelengths.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SampledSpectrum {
+    pub values: [f64; N_SAMPLES],
+}
+
+impl SampledSpectrum {
+    pub fn splat(value: f64) -> Self {
+        Self {
+            values: [value; N_SAMPLES],
+        }
+    }
+
+    // Upsamples a linear RGB color to a smooth spectrum, clamped at zero.
+    pub fn from_rgb(rgb: Color, wavelengths: &SampledWavelengths) -> Self {
+        Self {
+            values: wavelengths
+                .lambda
+                .map(|lambda| rgb_to_spectrum(rgb, lambda).max(0.0)),
+        }
+    }
+
+    // Upsamples a reflectance, relative to the spectrum of white, so that gray surfaces
+    // reflect every wavelength equally and repeated bounces don't drift in hue.
+    pub fn from_reflectance(rgb: Color, wavelengths: &SampledWavelengths) -> Self {
+        let white = Color::new(1.0, 1.0, 1.0);
+        Self {
+            values: wavelengths.lambda.map(|lambda| {
+                (rgb_to_spectrum(rgb, lambda) / rgb_to_spectrum(white, lambda)).max(0.0)
+            }),
+        }
+    }
+
+    // The Monte Carlo estimate of this radiance as linear sRGB, through CIE XYZ.
+    pub fn to_rgb(&self, wavelengths: &SampledWavelengths) -> Color {
+        let mut xyz = Vec3::default();
+        for i in 0..N_SAMPLES {
+            if wavelengths.pdf[i] > 0.0 {
+                xyz += self.values[i] * cie_xyz(wavelengths.lambda[i]) / wavelengths.pdf[i];
+            }
+        }
+        xyz_to_linear_srgb(xyz / (N_SAMPLES as f64 * cie_y_integral()))
+    }
+}
+
+impl Add for SampledSpectrum {
+    type Output = Self;
+
+    fn add(self, o: Self) -> Self {
+        Self {
+            values: std::array::from_fn(|i| self.values[i] + o.values[i]),
+        }
+    }
+}
+
+impl AddAssign for SampledSpectrum {
+    fn add_assign(&mut self, o: Self) {
+        *self = *self + o;
+    }
+}
+
+impl Mul for SampledSpectrum {
+    type Output = Self;
+
+    fn mul(self, o: Self) -> Self {
+        Self {
+            values: std::array::from_fn(|i| self.values[i] * o.values[i]),
+        }
+    }
+}
+
+impl MulAssign for SampledSpectrum {
+    fn mul_assign(&mut self, o: Self) {
+        *self = *self * o;
+    }
+}
+
+// Wyman, Sloan and Shirley's multi-lobe Gaussian fit to the CIE 1931 2-degree observer.
+pub fn cie_xyz(lambda: f64) -> Vec3 {
+    let g = |mu: f64, sigma1: f64, sigma2: f64| {
+        let t = (lambda - mu) / if lambda < mu { sigma1 } else { sigma2 };
+        (-0.5 * t * t).exp()
+    };
+    Vec3::new(
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+pub fn xyz_to_linear_srgb(xyz: Vec3) -> Color {
+    Color::new(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    )
+}
+
+// Integrates `f` over the visible range in 1 nm steps.
+fn integrate(f: impl Fn(f64) -> Vec3) -> Vec3 {
+    let steps = (LAMBDA_MAX - LAMBDA_MIN) as usize;
+    (0..steps)
+        .map(|i| f(LAMBDA_MIN + i as f64 + 0.5))
+        .fold(Vec3::default(), |a, b| a + b)
+}
+
+fn cie_y_integral() -> f64 {
+    static Y: OnceLock<f64> = OnceLock::new();
+    *Y.get_or_init(|| integrate(cie_xyz).y)
+}
+
+// Smooth blue, green and red bands that sum to one at every wavelength.
+fn basis(lambda: f64) -> Vec3 {
+    let smoothstep = |a: f64, b: f64| {
+        let t = ((lambda - a) / (b - a)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    };
+    let blue = 1.0 - smoothstep(470.0, 510.0);
+    let red = smoothstep(570.0, 610.0);
+    Vec3::new(red, 1.0 - red - blue, blue)
+}
+
+// Maps linear RGB to weights of the red, green and blue bands such that the upsampled
+// spectrum converts back to the same RGB.
+fn rgb_to_basis() -> &'static [Vec3; 3] {
+    static M: OnceLock<[Vec3; 3]> = OnceLock::new();
+    M.get_or_init(|| {
+        // Columns of the forward matrix: the RGB of each band on its own.
+        let columns: [Vec3; 3] = std::array::from_fn(|i| {
+            let xyz = integrate(|lambda| basis(lambda)[i] * cie_xyz(lambda));
+            xyz_to_linear_srgb(xyz / cie_y_integral())
+        });
+        invert(columns)
+    })
+}
+
+// Inverts the 3x3 matrix with the given columns, returning the rows of the inverse.
+fn invert(c: [Vec3; 3]) -> [Vec3; 3] {
+    let rows = [c[1].cross(c[2]), c[2].cross(c[0]), c[0].cross(c[1])];
+    let det = c[0].dot(rows[0]);
+    rows.map(|r| r / det)
+}
+
+pub fn rgb_to_spectrum(rgb: Color, lambda: f64) -> f64 {
+    let m = rgb_to_basis();
+    let weights = Vec3::new(m[0].dot(rgb), m[1].dot(rgb), m[2].dot(rgb));
+    weights.dot(basis(lambda))
+}
+
+// The linear RGB of a reflectance spectrum under equal-energy light, scaled so that a
+// perfect reflector stays white. Sampled every 10 nm, which is plenty for the slowly
+// varying spectra of interference colors.
+pub fn reflectance_to_rgb(reflectance: impl Fn(f64) -> f64) -> Color {
+    let xyz = |f: &dyn Fn(f64) -> f64| {
+        (0..(LAMBDA_MAX - LAMBDA_MIN) as usize / 10)
+            .map(|i| LAMBDA_MIN + 10.0 * i as f64 + 5.0)
+            .map(|lambda| f(lambda) * cie_xyz(lambda))
+            .fold(Vec3::default(), |a, b| a + b)
+    };
+    static WHITE: OnceLock<Color> = OnceLock::new();
+    let white = WHITE.get_or_init(|| xyz_to_linear_srgb(xyz(&|_| 1.0)));
+    let rgb = xyz_to_linear_srgb(xyz(&reflectance));
+    Color::new(rgb.x / white.x, rgb.y / white.y, rgb.z / white.z)
+}
+
+// How a material's index of refraction varies with wavelength.
+#[derive(Clone, Copy, Debug)]
+pub enum Dispersion {
+    // n = a + b / lambda^2, with lambda in micrometers.
+    Cauchy { a: f64, b: f64 },
+    // n^2 = 1 + sum of b_i lambda^2 / (lambda^2 - c_i), with lambda in micrometers.
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+    // Schott N-BK7 crown glass.
+    pub fn bk7() -> Self {
+        Dispersion::Sellmeier {
+            b: [1.03961212, 0.231792344, 1.01046945],
+            c: [0.00600069867, 0.0200179144, 103.560653],
+        }
+    }
+
+    // Schott SF11 dense flint glass, which splits light much more strongly.
+    pub fn sf11() -> Self {
+        Dispersion::Sellmeier {
+            b: [1.73759695, 0.313747346, 1.89878101],
+            c: [0.013188707, 0.0623068142, 155.23629],
+        }
+    }
+
+    pub fn ior(&self, lambda_nm: f64) -> f64 {
+        let l2 = (lambda_nm / 1000.0).powi(2);
+        match self {
+            Dispersion::Cauchy { a, b } => a + b / l2,
+            Dispersion::Sellmeier { b, c } => {
+                (1.0 + (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum::<f64>()).sqrt()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgb_round_trip() {
+        for rgb in [
+            Color::new(1.0, 1.0, 1.0),
+            Color::new(0.8, 0.3, 0.1),
+            Color::new(0.1, 0.2, 0.7),
+        ] {
+            let xyz = integrate(|lambda| rgb_to_spectrum(rgb, lambda) * cie_xyz(lambda));
+            let back = xyz_to_linear_srgb(xyz / cie_y_integral());
+            assert!((back - rgb).length() < 1e-9, "{} became {}", rgb, back);
+        }
+    }
+
+    #[test]
+    fn test_hero_wavelength_estimate() {
+        let rgb = Color::new(0.8, 0.3, 0.1);
+        let n = 20_000;
+        let mut sum = Color::default();
+        for i in 0..n {
+            let wavelengths = SampledWavelengths::sample_uniform((i as f64 + 0.5) / n as f64);
+            sum += SampledSpectrum::from_rgb(rgb, &wavelengths).to_rgb(&wavelengths);
+        }
+        assert!((sum / n as f64 - rgb).length() < 1e-3);
+    }
+
+    #[test]
+    fn test_gray_reflectance_is_flat() {
+        let wavelengths = SampledWavelengths::sample_uniform(0.3);
+        let gray = SampledSpectrum::from_reflectance(Color::new(0.5, 0.5, 0.5), &wavelengths);
+        for value in gray.values {
+            assert!((value - 0.5).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_dispersion() {
+        // BK7 at the helium d line.
+        assert!((Dispersion::bk7().ior(587.56) - 1.5168).abs() < 1e-4);
+        let cauchy = Dispersion::Cauchy { a: 1.5, b: 0.004 };
+        assert!(cauchy.ior(450.0) > cauchy.ior(650.0));
+    }
+}
@@ -1,4 +1,6 @@
+use crate::perlin::Perlin;
 use crate::vec3::{Color, Point3};
+use std::path::Path;
 use std::sync::Arc;
 
 pub trait Texture: Send + Sync {
@@ -48,3 +50,265 @@ impl Texture for CheckerTexture {
         }
     }
 }
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WrapMode {
+    Repeat,
+    Clamp,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FilterMode {
+    Nearest,
+    Bilinear,
+}
+
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    texels: Vec<Color>, // linear RGB, row 0 at the top of the image
+    wrap: WrapMode,
+    filter: FilterMode,
+}
+
+impl ImageTexture {
+    // Loads a color image whose 8-bit values are sRGB encoded.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, image::ImageError> {
+        Self::load(path.as_ref(), true)
+    }
+
+    // Loads an image holding data (e.g. normals or heights) stored without gamma.
+    pub fn open_linear(path: impl AsRef<Path>) -> Result<Self, image::ImageError> {
+        Self::load(path.as_ref(), false)
+    }
+
+    // Loads the alpha channel of an image as gray, e.g. to cut out a foliage card with
+    // `AlphaMask`. Images without one come out fully opaque.
+    pub fn open_alpha(path: impl AsRef<Path>) -> Result<Self, image::ImageError> {
+        let rgba = image::open(path.as_ref())?.to_rgba32f();
+        let texels = rgba
+            .pixels()
+            .map(|p| {
+                let a = p[3] as f64;
+                Color::new(a, a, a)
+            })
+            .collect();
+        Ok(Self::from_texels(
+            rgba.width() as usize,
+            rgba.height() as usize,
+            texels,
+        ))
+    }
+
+    fn load(path: &Path, srgb: bool) -> Result<Self, image::ImageError> {
+        let img = image::open(path)?;
+        // Floating point formats such as Radiance HDR are linear already.
+        let srgb = srgb && !matches!(img, image::DynamicImage::ImageRgb32F(_));
+        let rgb = img.to_rgb32f();
+        let texels = rgb
+            .pixels()
+            .map(|p| {
+                let decode = |c: f32| {
+                    if srgb {
+                        srgb_to_linear(c as f64)
+                    } else {
+                        c as f64
+                    }
+                };
+                Color::new(decode(p[0]), decode(p[1]), decode(p[2]))
+            })
+            .collect();
+        Ok(Self::from_texels(
+            rgb.width() as usize,
+            rgb.height() as usize,
+            texels,
+        ))
+    }
+
+    pub fn from_texels(width: usize, height: usize, texels: Vec<Color>) -> Self {
+        assert_eq!(
+            texels.len(),
+            width * height,
+            "texel count must be width * height"
+        );
+        Self {
+            width,
+            height,
+            texels,
+            wrap: WrapMode::Repeat,
+            filter: FilterMode::Bilinear,
+        }
+    }
+
+    pub fn with_wrap(mut self, wrap: WrapMode) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: FilterMode) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn texel(&self, x: i64, y: i64) -> Color {
+        let wrap = |i: i64, n: usize| match self.wrap {
+            WrapMode::Repeat => i.rem_euclid(n as i64) as usize,
+            WrapMode::Clamp => i.clamp(0, n as i64 - 1) as usize,
+        };
+        self.texels[wrap(y, self.height) * self.width + wrap(x, self.width)]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &Point3) -> Color {
+        // Flip v so that v = 1 is the top row of the image.
+        let x = u * self.width as f64;
+        let y = (1.0 - v) * self.height as f64;
+
+        match self.filter {
+            FilterMode::Nearest => self.texel(x.floor() as i64, y.floor() as i64),
+            FilterMode::Bilinear => {
+                let x = x - 0.5;
+                let y = y - 0.5;
+                let x0 = x.floor();
+                let y0 = y.floor();
+                let fx = x - x0;
+                let fy = y - y0;
+                let (x0, y0) = (x0 as i64, y0 as i64);
+
+                let top = (1.0 - fx) * self.texel(x0, y0) + fx * self.texel(x0 + 1, y0);
+                let bottom = (1.0 - fx) * self.texel(x0, y0 + 1) + fx * self.texel(x0 + 1, y0 + 1);
+                (1.0 - fy) * top + fy * bottom
+            }
+        }
+    }
+}
+
+pub fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub struct NoiseTexture {
+    noise: Perlin,
+    scale: f64,
+}
+
+impl NoiseTexture {
+    pub fn new(scale: f64, seed: u64) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+        Color::new(1.0, 1.0, 1.0) * 0.5 * (1.0 + self.noise.noise(&(self.scale * *p)))
+    }
+}
+
+pub struct MarbleTexture {
+    noise: Perlin,
+    scale: f64,
+}
+
+impl MarbleTexture {
+    pub fn new(scale: f64, seed: u64) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale,
+        }
+    }
+}
+
+impl Texture for MarbleTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+        // Turbulence shifts the phase of sine stripes along z into veins.
+        Color::new(1.0, 1.0, 1.0)
+            * 0.5
+            * (1.0 + (self.scale * p.z + 10.0 * self.noise.turb(p, 7)).sin())
+    }
+}
+
+pub struct WoodTexture {
+    noise: Perlin,
+    scale: f64,
+    light: Color,
+    dark: Color,
+}
+
+impl WoodTexture {
+    pub fn new(scale: f64, seed: u64, light: Color, dark: Color) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale,
+            light,
+            dark,
+        }
+    }
+}
+
+impl Texture for WoodTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+        // Concentric rings around the y axis, distorted by turbulence.
+        let distance = (p.x * p.x + p.z * p.z).sqrt() * self.scale;
+        let rings = distance + 2.0 * self.noise.turb(&(0.5 * *p), 4);
+        let t = rings - rings.floor();
+        (1.0 - t) * self.light + t * self.dark
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_by_one() -> ImageTexture {
+        ImageTexture::from_texels(
+            2,
+            1,
+            vec![Color::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0)],
+        )
+    }
+
+    #[test]
+    fn test_bilinear_blends_between_texel_centers() {
+        let tex = two_by_one().with_wrap(WrapMode::Clamp);
+        let p = Point3::default();
+        assert_eq!(tex.value(0.25, 0.5, &p), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(tex.value(0.5, 0.5, &p), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(tex.value(0.75, 0.5, &p), Color::new(1.0, 1.0, 1.0));
+        // Clamping keeps the edge texel; repeating blends with the opposite edge.
+        assert_eq!(tex.value(0.0, 0.5, &p), Color::new(0.0, 0.0, 0.0));
+        let tex = two_by_one().with_wrap(WrapMode::Repeat);
+        assert_eq!(tex.value(0.0, 0.5, &p), Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn test_nearest_and_wrap() {
+        let tex = two_by_one().with_filter(FilterMode::Nearest);
+        let p = Point3::default();
+        assert_eq!(tex.value(0.1, 0.5, &p), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(tex.value(1.6, 0.5, &p), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(tex.value(-0.4, 0.5, &p), Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_srgb_to_linear() {
+        assert_eq!(srgb_to_linear(0.0), 0.0);
+        assert!((srgb_to_linear(1.0) - 1.0).abs() < 1e-12);
+        assert!((srgb_to_linear(0.5) - 0.214).abs() < 1e-3);
+    }
+}
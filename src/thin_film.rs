@@ -0,0 +1,91 @@
+use crate::rtweekend::PI;
+
+// A transparent film, like soap or oil, whose reflections off its two faces interfere
+// and tint the reflectance of the surface beneath it.
+#[derive(Clone, Copy, Debug)]
+pub struct ThinFilm {
+    pub thickness: f64, // nm
+    pub ior: f64,
+}
+
+impl ThinFilm {
+    pub fn new(thickness: f64, ior: f64) -> Self {
+        Self { thickness, ior }
+    }
+
+    // Reflectance at wavelength `lambda` of the film lying between a medium of index `n1`,
+    // on the incident side, and a dielectric of index `n3`.
+    pub fn reflectance(&self, cos_theta_i: f64, n1: f64, n3: f64, lambda: f64) -> f64 {
+        let cos1 = cos_theta_i.clamp(0.0, 1.0);
+        let sin2_1 = 1.0 - cos1 * cos1;
+        let sin2_3 = (n1 / n3).powi(2) * sin2_1;
+        if sin2_3 >= 1.0 {
+            return 1.0;
+        }
+        let cos3 = (1.0 - sin2_3).sqrt();
+        self.airy(cos1, n1, lambda, |n2, cos2| {
+            (
+                (n2 * cos2 - n3 * cos3) / (n2 * cos2 + n3 * cos3),
+                (n3 * cos2 - n2 * cos3) / (n3 * cos2 + n2 * cos3),
+            )
+        })
+    }
+
+    // Reflectance at wavelength `lambda` of the film over a metal that reflects
+    // `substrate` on its own. The metal is taken to reflect with a phase shift of pi.
+    pub fn reflectance_over_metal(
+        &self,
+        cos_theta_i: f64,
+        n1: f64,
+        substrate: f64,
+        lambda: f64,
+    ) -> f64 {
+        let r = -substrate.clamp(0.0, 1.0).sqrt();
+        self.airy(cos_theta_i.clamp(0.0, 1.0), n1, lambda, |_, _| (r, r))
+    }
+
+    // Sums the multiple reflections inside the film, averaged over both polarizations.
+    // `r23` gives the s and p amplitude reflection coefficients of the lower face from
+    // the film's index and cosine.
+    fn airy(&self, cos1: f64, n1: f64, lambda: f64, r23: impl Fn(f64, f64) -> (f64, f64)) -> f64 {
+        let n2 = self.ior;
+        let sin2_2 = (n1 / n2).powi(2) * (1.0 - cos1 * cos1);
+        if sin2_2 >= 1.0 {
+            return 1.0;
+        }
+        let cos2 = (1.0 - sin2_2).sqrt();
+        let r12_s = (n1 * cos1 - n2 * cos2) / (n1 * cos1 + n2 * cos2);
+        let r12_p = (n2 * cos1 - n1 * cos2) / (n2 * cos1 + n1 * cos2);
+        let (r23_s, r23_p) = r23(n2, cos2);
+
+        let cos_delta = (4.0 * PI * n2 * self.thickness * cos2 / lambda).cos();
+        let airy = |a: f64, b: f64| {
+            let cross = 2.0 * a * b * cos_delta;
+            ((a * a + b * b + cross) / (1.0 + a * a * b * b + cross)).clamp(0.0, 1.0)
+        };
+        (airy(r12_s, r23_s) + airy(r12_p, r23_p)) / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::microfacet::fresnel_dielectric;
+
+    #[test]
+    fn test_thin_film_reflectance() {
+        // A film of no thickness leaves the bare interface.
+        let film = ThinFilm::new(0.0, 1.33);
+        for cos in [1.0, 0.7, 0.2] {
+            let r = film.reflectance(cos, 1.0, 1.5, 550.0);
+            assert!((r - fresnel_dielectric(cos, 1.5)).abs() < 1e-9);
+        }
+
+        // A quarter-wave coating of index sqrt(n) cancels the reflection at its design
+        // wavelength, but not at others.
+        let n_film = 1.5f64.sqrt();
+        let coating = ThinFilm::new(550.0 / (4.0 * n_film), n_film);
+        assert!(coating.reflectance(1.0, 1.0, 1.5, 550.0) < 1e-9);
+        assert!(coating.reflectance(1.0, 1.0, 1.5, 400.0) > 0.005);
+    }
+}
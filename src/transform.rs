@@ -0,0 +1,291 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::mat4::Mat4;
+use crate::ray::Ray;
+use crate::rtweekend::degrees_to_radians;
+use crate::vec3::{Point3, Vec3};
+use std::sync::Arc;
+
+pub struct Translate {
+    ptr: Arc<dyn Hittable>,
+    offset: Vec3,
+}
+
+impl Translate {
+    pub fn new(ptr: Arc<dyn Hittable>, offset: Vec3) -> Self {
+        Self { ptr, offset }
+    }
+}
+
+impl Hittable for Translate {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let moved_r = Ray::new(r.origin - self.offset, r.direction, r.time);
+        let mut rec = self.ptr.hit(&moved_r, t_min, t_max)?;
+        rec.p += self.offset;
+        Some(rec)
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
+        let bbox = self.ptr.bounding_box(t0, t1)?;
+        Some(Aabb::from_points(
+            bbox.min + self.offset,
+            bbox.max + self.offset,
+        ))
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        self.ptr.pdf_value(&(*origin - self.offset), direction)
+    }
+
+    fn random(&self, origin: &Point3) -> Vec3 {
+        self.ptr.random(&(*origin - self.offset))
+    }
+}
+
+pub struct RotateY {
+    ptr: Arc<dyn Hittable>,
+    sin_theta: f64,
+    cos_theta: f64,
+    bbox: Option<Aabb>,
+}
+
+impl RotateY {
+    pub fn new(ptr: Arc<dyn Hittable>, angle: f64) -> Self {
+        let radians = degrees_to_radians(angle);
+        let sin_theta = radians.sin();
+        let cos_theta = radians.cos();
+        let bbox = ptr
+            .bounding_box(0.0, 1.0)
+            .map(|b| transformed_box(&b, |p| rotate_y(p, sin_theta, cos_theta)));
+
+        Self {
+            ptr,
+            sin_theta,
+            cos_theta,
+            bbox,
+        }
+    }
+}
+
+fn rotate_y(p: Vec3, sin_theta: f64, cos_theta: f64) -> Vec3 {
+    Vec3::new(
+        cos_theta * p.x + sin_theta * p.z,
+        p.y,
+        -sin_theta * p.x + cos_theta * p.z,
+    )
+}
+
+impl Hittable for RotateY {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // Rotate the ray into object space by -theta.
+        let origin = rotate_y(r.origin, -self.sin_theta, self.cos_theta);
+        let direction = rotate_y(r.direction, -self.sin_theta, self.cos_theta);
+        let rotated_r = Ray::new(origin, direction, r.time);
+
+        let mut rec = self.ptr.hit(&rotated_r, t_min, t_max)?;
+        rec.p = rotate_y(rec.p, self.sin_theta, self.cos_theta);
+        rec.normal = rotate_y(rec.normal, self.sin_theta, self.cos_theta);
+        rec.tangent = rotate_y(rec.tangent, self.sin_theta, self.cos_theta);
+        rec.bitangent = rotate_y(rec.bitangent, self.sin_theta, self.cos_theta);
+        Some(rec)
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<Aabb> {
+        self.bbox
+    }
+
+    // Rotations keep solid angles, so the object's density carries over as it is.
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        self.ptr.pdf_value(
+            &rotate_y(*origin, -self.sin_theta, self.cos_theta),
+            &rotate_y(*direction, -self.sin_theta, self.cos_theta),
+        )
+    }
+
+    fn random(&self, origin: &Point3) -> Vec3 {
+        let direction = self
+            .ptr
+            .random(&rotate_y(*origin, -self.sin_theta, self.cos_theta));
+        rotate_y(direction, self.sin_theta, self.cos_theta)
+    }
+}
+
+// A general affine instance: object space is mapped to world space by `matrix`.
+pub struct Transform {
+    ptr: Arc<dyn Hittable>,
+    matrix: Mat4,
+    inverse: Mat4,
+    normal_matrix: Mat4,
+}
+
+impl Transform {
+    pub fn new(ptr: Arc<dyn Hittable>, matrix: Mat4) -> Self {
+        let inverse = matrix
+            .inverse()
+            .expect("Transform matrix must be invertible.");
+        Self {
+            ptr,
+            matrix,
+            inverse,
+            normal_matrix: inverse.transpose(),
+        }
+    }
+}
+
+impl Hittable for Transform {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // The direction is not renormalized, so t is the same in both spaces.
+        let object_r = Ray::new(
+            self.inverse.transform_point(r.origin),
+            self.inverse.transform_vector(r.direction),
+            r.time,
+        );
+
+        let mut rec = self.ptr.hit(&object_r, t_min, t_max)?;
+        rec.p = self.matrix.transform_point(rec.p);
+        rec.normal = self
+            .normal_matrix
+            .transform_vector(rec.normal)
+            .unit_vector();
+        // Tangents lie in the surface, so they map like any other direction.
+        rec.tangent = self.matrix.transform_vector(rec.tangent);
+        rec.bitangent = self.matrix.transform_vector(rec.bitangent);
+        Some(rec)
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
+        let bbox = self.ptr.bounding_box(t0, t1)?;
+        Some(transformed_box(&bbox, |p| self.matrix.transform_point(p)))
+    }
+
+    // The object's density over its own directions, times how much the inverse map
+    // stretches solid angle around `direction`: |det M^-1| / |M^-1 w|^3 for unit w.
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        let object_direction = self.inverse.transform_vector(direction.unit_vector());
+        let pdf = self
+            .ptr
+            .pdf_value(&self.inverse.transform_point(*origin), &object_direction);
+        pdf * self.inverse.linear_determinant().abs() / object_direction.length().powi(3)
+    }
+
+    fn random(&self, origin: &Point3) -> Vec3 {
+        let direction = self.ptr.random(&self.inverse.transform_point(*origin));
+        self.matrix.transform_vector(direction)
+    }
+}
+
+// The world-space box around the eight transformed corners of `bbox`.
+fn transformed_box(bbox: &Aabb, f: impl Fn(Point3) -> Point3) -> Aabb {
+    let mut min = Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+    let mut max = Point3::new(-f64::INFINITY, -f64::INFINITY, -f64::INFINITY);
+
+    for i in 0..2 {
+        for j in 0..2 {
+            for k in 0..2 {
+                let corner = Point3::new(
+                    if i == 0 { bbox.min.x } else { bbox.max.x },
+                    if j == 0 { bbox.min.y } else { bbox.max.y },
+                    if k == 0 { bbox.min.z } else { bbox.max.z },
+                );
+                let p = f(corner);
+                min = Point3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+                max = Point3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+            }
+        }
+    }
+    Aabb::from_points(min, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarect::XzRect;
+    use crate::hittable::Sphere;
+    use crate::material::{Lambertian, Material};
+    use crate::rtweekend::PI;
+    use crate::texture::SolidColor;
+
+    fn gray() -> Arc<dyn Material> {
+        Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+            0.5, 0.5, 0.5,
+        ))))
+    }
+
+    fn unit_sphere() -> Arc<dyn Hittable> {
+        Arc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, gray()))
+    }
+
+    fn near(a: Vec3, b: Vec3) -> bool {
+        (a - b).length() < 1e-9
+    }
+
+    #[test]
+    fn test_instances_map_hits_to_world_space() {
+        let moved = Translate::new(unit_sphere(), Vec3::new(0.0, 0.0, 5.0));
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let rec = moved.hit(&r, 0.001, f64::INFINITY).unwrap();
+        assert!(near(rec.p, Point3::new(0.0, 0.0, 4.0)));
+        assert!(near(rec.normal, Vec3::new(0.0, 0.0, -1.0)));
+        assert!(rec.front_face);
+
+        // A floor tile turned a quarter turn, hit from below.
+        let tile = Arc::new(XzRect::new(0.0, 2.0, -0.5, 0.5, 1.0, gray()));
+        let turned = RotateY::new(tile, 90.0);
+        let r = Ray::new(Point3::new(0.0, 0.0, -1.5), Vec3::new(0.0, 1.0, 0.0), 0.0);
+        let rec = turned.hit(&r, 0.001, f64::INFINITY).unwrap();
+        assert!(near(rec.p, Point3::new(0.0, 1.0, -1.5)));
+        assert!(near(rec.normal, Vec3::new(0.0, -1.0, 0.0)));
+        assert!(!rec.front_face);
+        assert!((rec.u - 0.75).abs() < 1e-9);
+
+        // An ellipsoid x^2/4 + y^2 + z^2 = 1, whose normals follow (x/4, y, z).
+        let ellipsoid = Transform::new(unit_sphere(), Mat4::scaling(Vec3::new(2.0, 1.0, 1.0)));
+        let r = Ray::new(Point3::new(-5.0, 0.5, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let rec = ellipsoid.hit(&r, 0.001, f64::INFINITY).unwrap();
+        let x = -2.0 * 0.75f64.sqrt();
+        assert!(near(rec.p, Point3::new(x, 0.5, 0.0)));
+        assert!(near(rec.normal, Vec3::new(x / 4.0, 0.5, 0.0).unit_vector()));
+        assert!(rec.front_face);
+        let inside = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let rec = ellipsoid.hit(&inside, 0.001, f64::INFINITY).unwrap();
+        assert!((rec.t - 2.0).abs() < 1e-9);
+        assert!(!rec.front_face);
+        assert!(near(rec.normal, Vec3::new(-1.0, 0.0, 0.0)));
+    }
+
+    // The density of directions toward `light` from the origin integrates to one, and
+    // the light's own samples fall where that density is positive.
+    fn assert_light_pdf_normalized(light: &dyn Hittable) {
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        let n = 400_000;
+        let samples: Vec<f64> = (0..n)
+            .map(|_| 4.0 * PI * light.pdf_value(&origin, &Vec3::random_unit_vector()))
+            .collect();
+        let integral = samples.iter().sum::<f64>() / n as f64;
+        let variance = samples.iter().map(|x| (x - integral).powi(2)).sum::<f64>() / (n - 1) as f64;
+        let standard_error = (variance / n as f64).sqrt();
+        assert!(
+            (integral - 1.0).abs() < 5.0 * standard_error,
+            "integral {} +- {}",
+            integral,
+            standard_error
+        );
+        for _ in 0..1000 {
+            assert!(light.pdf_value(&origin, &light.random(&origin)) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_instanced_lights_keep_their_density() {
+        let tile = || Arc::new(XzRect::new(-1.0, 1.0, -0.5, 1.5, 1.0, gray()));
+        assert_light_pdf_normalized(&RotateY::new(tile(), 30.0));
+        assert_light_pdf_normalized(&Transform::new(
+            tile(),
+            Mat4::rotation_x(20.0) * Mat4::scaling(Vec3::new(1.5, 1.0, 0.7)),
+        ));
+        assert_light_pdf_normalized(&Transform::new(
+            unit_sphere(),
+            Mat4::translation(Vec3::new(0.0, 0.0, 3.0)) * Mat4::scaling(Vec3::new(1.0, 0.5, 0.5)),
+        ));
+    }
+}
@@ -0,0 +1,344 @@
+use crate::aabb::Aabb;
+use crate::bvh::BvhNode;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use std::sync::Arc;
+
+// Half thickness given to the bounding box of a triangle lying in an axis plane.
+const PAD: f64 = 0.0001;
+
+pub struct Triangle {
+    vertices: [Point3; 3],
+    normals: Option<[Vec3; 3]>,
+    uvs: Option<[(f64, f64); 3]>,
+    mat_ptr: Arc<dyn Material>,
+}
+
+impl Triangle {
+    pub fn new(v0: Point3, v1: Point3, v2: Point3, mat_ptr: Arc<dyn Material>) -> Self {
+        Self {
+            vertices: [v0, v1, v2],
+            normals: None,
+            uvs: None,
+            mat_ptr,
+        }
+    }
+
+    pub fn with_normals(mut self, normals: [Vec3; 3]) -> Self {
+        self.normals = Some(normals);
+        self
+    }
+
+    pub fn with_uvs(mut self, uvs: [(f64, f64); 3]) -> Self {
+        self.uvs = Some(uvs);
+        self
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        hit_triangle(
+            r,
+            t_min,
+            t_max,
+            &self.vertices,
+            self.normals.as_ref(),
+            self.uvs.as_ref(),
+            &self.mat_ptr,
+        )
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<Aabb> {
+        Some(triangle_box(&self.vertices))
+    }
+}
+
+// Vertex buffers shared by every triangle of a mesh. Normals and UVs, when present,
+// are indexed by the same indices as the positions.
+pub struct MeshData {
+    pub positions: Vec<Point3>,
+    pub normals: Option<Vec<Vec3>>,
+    pub uvs: Option<Vec<(f64, f64)>>,
+    pub indices: Vec<[usize; 3]>,
+    pub mat_ptr: Arc<dyn Material>,
+}
+
+pub struct TriangleMesh {
+    bvh: BvhNode,
+}
+
+impl TriangleMesh {
+    pub fn new(mesh: MeshData) -> Self {
+        let mesh = Arc::new(mesh);
+        let mut triangles: Vec<Arc<dyn Hittable>> = (0..mesh.indices.len())
+            .map(|index| {
+                Arc::new(MeshTriangle {
+                    mesh: Arc::clone(&mesh),
+                    index,
+                }) as Arc<dyn Hittable>
+            })
+            .collect();
+        assert!(
+            !triangles.is_empty(),
+            "TriangleMesh needs at least one face"
+        );
+
+        Self {
+            bvh: BvhNode::sah(&mut triangles, 0.0, 1.0),
+        }
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.bvh.hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<Aabb> {
+        self.bvh.bounding_box(t0, t1)
+    }
+}
+
+struct MeshTriangle {
+    mesh: Arc<MeshData>,
+    index: usize,
+}
+
+impl MeshTriangle {
+    fn vertices(&self) -> [Point3; 3] {
+        let [i0, i1, i2] = self.mesh.indices[self.index];
+        [
+            self.mesh.positions[i0],
+            self.mesh.positions[i1],
+            self.mesh.positions[i2],
+        ]
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let [i0, i1, i2] = self.mesh.indices[self.index];
+        let normals = self.mesh.normals.as_ref().map(|n| [n[i0], n[i1], n[i2]]);
+        let uvs = self.mesh.uvs.as_ref().map(|uv| [uv[i0], uv[i1], uv[i2]]);
+        hit_triangle(
+            r,
+            t_min,
+            t_max,
+            &self.vertices(),
+            normals.as_ref(),
+            uvs.as_ref(),
+            &self.mesh.mat_ptr,
+        )
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<Aabb> {
+        Some(triangle_box(&self.vertices()))
+    }
+}
+
+fn triangle_box(v: &[Point3; 3]) -> Aabb {
+    let pad = Vec3::new(PAD, PAD, PAD);
+    let min = Point3::new(
+        v[0].x.min(v[1].x).min(v[2].x),
+        v[0].y.min(v[1].y).min(v[2].y),
+        v[0].z.min(v[1].z).min(v[2].z),
+    );
+    let max = Point3::new(
+        v[0].x.max(v[1].x).max(v[2].x),
+        v[0].y.max(v[1].y).max(v[2].y),
+        v[0].z.max(v[1].z).max(v[2].z),
+    );
+    Aabb::from_points(min - pad, max + pad)
+}
+
+// Watertight ray/triangle intersection (Woop, Benthin and Wald, 2013).
+// Returns the ray parameter and the barycentric weights of the three vertices.
+fn intersect(r: &Ray, t_min: f64, t_max: f64, v: &[Point3; 3]) -> Option<(f64, [f64; 3])> {
+    let d = r.direction;
+
+    // Pick the dominant axis of the ray direction as z, keeping the winding.
+    let kz = if d.x.abs() > d.y.abs() {
+        if d.x.abs() > d.z.abs() { 0 } else { 2 }
+    } else if d.y.abs() > d.z.abs() {
+        1
+    } else {
+        2
+    };
+    let mut kx = (kz + 1) % 3;
+    let mut ky = (kx + 1) % 3;
+    if d[kz] < 0.0 {
+        std::mem::swap(&mut kx, &mut ky);
+    }
+
+    let sx = d[kx] / d[kz];
+    let sy = d[ky] / d[kz];
+    let sz = 1.0 / d[kz];
+
+    let a = v[0] - r.origin;
+    let b = v[1] - r.origin;
+    let c = v[2] - r.origin;
+
+    let ax = a[kx] - sx * a[kz];
+    let ay = a[ky] - sy * a[kz];
+    let bx = b[kx] - sx * b[kz];
+    let by = b[ky] - sy * b[kz];
+    let cx = c[kx] - sx * c[kz];
+    let cy = c[ky] - sy * c[kz];
+
+    let u = cx * by - cy * bx;
+    let v_ = ax * cy - ay * cx;
+    let w = bx * ay - by * ax;
+
+    if (u < 0.0 || v_ < 0.0 || w < 0.0) && (u > 0.0 || v_ > 0.0 || w > 0.0) {
+        return None;
+    }
+
+    let det = u + v_ + w;
+    if det == 0.0 {
+        return None;
+    }
+
+    let az = sz * a[kz];
+    let bz = sz * b[kz];
+    let cz = sz * c[kz];
+    let t = (u * az + v_ * bz + w * cz) / det;
+    if t < t_min || t > t_max {
+        return None;
+    }
+
+    Some((t, [u / det, v_ / det, w / det]))
+}
+
+fn hit_triangle(
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+    v: &[Point3; 3],
+    normals: Option<&[Vec3; 3]>,
+    uvs: Option<&[(f64, f64); 3]>,
+    mat_ptr: &Arc<dyn Material>,
+) -> Option<HitRecord> {
+    let (t, b) = intersect(r, t_min, t_max, v)?;
+
+    // Without texture coordinates, u and v are the barycentrics of v[1] and v[2].
+    let uv = uvs.copied().unwrap_or([(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]);
+    let u = b[0] * uv[0].0 + b[1] * uv[1].0 + b[2] * uv[2].0;
+    let v_ = b[0] * uv[0].1 + b[1] * uv[1].1 + b[2] * uv[2].1;
+    let outward_normal = (v[1] - v[0]).cross(v[2] - v[0]).unit_vector();
+    let (tangent, bitangent) = triangle_tangents(v, &uv, outward_normal);
+
+    let mut rec = HitRecord {
+        p: r.at(t),
+        t,
+        u,
+        v: v_,
+        normal: Vec3::new(0.0, 0.0, 0.0),
+        tangent,
+        bitangent,
+        front_face: false,
+        mat_ptr: Arc::clone(mat_ptr),
+    };
+    rec.set_face_normal(r, outward_normal);
+
+    // The side is decided by the geometric normal; the interpolated normal only shades.
+    if let Some(n) = normals {
+        let shading_normal = (b[0] * n[0] + b[1] * n[1] + b[2] * n[2]).unit_vector();
+        rec.normal = if rec.front_face {
+            shading_normal
+        } else {
+            -shading_normal
+        };
+    }
+    Some(rec)
+}
+
+// dp/du and dp/dv across the triangle, falling back to an arbitrary frame around
+// `normal` where the texture coordinates are degenerate.
+fn triangle_tangents(v: &[Point3; 3], uv: &[(f64, f64); 3], normal: Vec3) -> (Vec3, Vec3) {
+    let (du02, dv02) = (uv[0].0 - uv[2].0, uv[0].1 - uv[2].1);
+    let (du12, dv12) = (uv[1].0 - uv[2].0, uv[1].1 - uv[2].1);
+    let (dp02, dp12) = (v[0] - v[2], v[1] - v[2]);
+    let det = du02 * dv12 - dv02 * du12;
+    if det.abs() > 1e-12 {
+        let dpdu = (dv12 * dp02 - dv02 * dp12) / det;
+        let dpdv = (du02 * dp12 - du12 * dp02) / det;
+        if dpdu.cross(dpdv).length_squared() > 0.0 {
+            return (dpdu, dpdv);
+        }
+    }
+    let uvw = Onb::build_from_w(normal);
+    (uvw.u(), uvw.v())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::texture::SolidColor;
+
+    fn gray() -> Arc<dyn Material> {
+        Arc::new(Lambertian::new(Arc::new(SolidColor::from_rgb(
+            0.5, 0.5, 0.5,
+        ))))
+    }
+
+    #[test]
+    fn test_hit_reports_barycentric_uv() {
+        let tri = Triangle::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+            gray(),
+        );
+        let r = Ray::new(Point3::new(0.25, 0.5, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let rec = tri.hit(&r, 0.001, f64::INFINITY).unwrap();
+        assert!((rec.t - 1.0).abs() < 1e-9);
+        assert!((rec.u - 0.25).abs() < 1e-9);
+        assert!((rec.v - 0.5).abs() < 1e-9);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert!((rec.tangent - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-9);
+        assert!((rec.bitangent - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+    }
+
+    #[test]
+    fn test_miss_outside_triangle() {
+        let tri = Triangle::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+            gray(),
+        );
+        let r = Ray::new(Point3::new(0.75, 0.75, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!(tri.hit(&r, 0.001, f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_shared_edge_is_watertight() {
+        // A unit quad split along its diagonal; rays through the diagonal must hit.
+        let mesh = TriangleMesh::new(MeshData {
+            positions: vec![
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(1.0, 1.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+            ],
+            normals: None,
+            uvs: Some(vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]),
+            indices: vec![[0, 1, 2], [0, 2, 3]],
+            mat_ptr: gray(),
+        });
+        for i in 1..100 {
+            let s = i as f64 / 100.0;
+            let direction = Vec3::new(0.1, -0.2, -1.0);
+            let r = Ray::new(Point3::new(s, s, 0.0) - 2.0 * direction, direction, 0.0);
+            let rec = mesh.hit(&r, 0.001, f64::INFINITY);
+            assert!(rec.is_some(), "ray through the diagonal at {} missed", s);
+            let rec = rec.unwrap();
+            assert!((rec.u - s).abs() < 1e-9 && (rec.v - s).abs() < 1e-9);
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use crate::rtweekend::{random_double, random_double_range};
 use std::f64::consts::PI;
 use std::fmt;
-use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub};
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul, MulAssign, Neg, Sub};
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Vec3 {
@@ -80,6 +80,19 @@ impl Vec3 {
         }
     }
 
+    // Cosine-weighted direction on the hemisphere around +z.
+    pub fn random_cosine_direction() -> Vec3 {
+        let r1 = random_double();
+        let r2 = random_double();
+        let phi = 2.0 * PI * r1;
+
+        Self {
+            x: phi.cos() * r2.sqrt(),
+            y: phi.sin() * r2.sqrt(),
+            z: (1.0 - r2).sqrt(),
+        }
+    }
+
     pub fn reflect(v: &Vec3, n: &Vec3) -> Vec3 {
         *v - (*n * 2.0 * v.dot(*n))
     }
@@ -126,6 +139,19 @@ impl Default for Vec3 {
     }
 }
 
+impl Index<usize> for Vec3 {
+    type Output = f64;
+
+    fn index(&self, axis: usize) -> &f64 {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vec3 index out of range: {}", axis),
+        }
+    }
+}
+
 impl Neg for Vec3 {
     type Output = Self;
 